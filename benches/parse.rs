@@ -0,0 +1,64 @@
+//! Parse throughput of `RateLimit::new` for typical responses
+//!
+//! Run with `cargo bench`.
+use std::str::FromStr;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use indoc::indoc;
+use rate_limits::{CaseSensitiveHeaderMap, RateLimit};
+
+/// Response headers, including some which are not related to rate limits
+const RESPONSES: &[(&str, &str)] = &[
+    (
+        "github",
+        indoc! {r#"
+            content-type: application/json; charset=utf-8
+            cache-control: private, max-age=60, s-maxage=60
+            etag: "a00049ba79152d03380c34652f2cb612"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+            x-ratelimit-used: 13
+            x-ratelimit-resource: core
+        "#},
+    ),
+    (
+        "gitlab",
+        indoc! {"
+            Content-Type: application/json
+            RateLimit-Limit: 60
+            RateLimit-Observed: 67
+            RateLimit-Remaining: 0
+            RateLimit-Reset: 1609844400
+            RateLimit-ResetTime: Tue, 05 Jan 2021 11:00:00 GMT
+        "},
+    ),
+    (
+        "retry-after",
+        indoc! {"
+            Content-Type: text/html
+            Retry-After: 30
+        "},
+    ),
+    (
+        "none",
+        indoc! {"
+            Content-Type: text/html
+            Cache-Control: no-cache
+        "},
+    ),
+];
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for &(name, headers) in RESPONSES {
+        let headers = CaseSensitiveHeaderMap::from_str(headers).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| RateLimit::new(black_box(headers.clone())));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+#![no_main]
+
+use std::str::FromStr;
+
+use libfuzzer_sys::fuzz_target;
+use rate_limits::{CaseSensitiveHeaderMap, RateLimit};
+
+// Parsing arbitrary (possibly adversarial) headers must never panic.
+// Invalid input has to be reported as an `Error` instead.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(headers) = std::str::from_utf8(data) {
+        let _ = RateLimit::from_str(headers);
+        let _ = RateLimit::new(CaseSensitiveHeaderMap::from(headers));
+    }
+});
@@ -0,0 +1,104 @@
+//! Incremental parsing of rate limits from individual headers.
+use ::headers::HeaderValue;
+
+use crate::casesensitive_headermap::CaseSensitiveHeaderMap;
+use crate::error::{Error, Result};
+use crate::RateLimit;
+
+/// Builder to parse a [`RateLimit`] from headers which arrive one at a time
+///
+/// This is useful for streaming HTTP parsers, which report headers with
+/// callbacks instead of a complete `http::HeaderMap`.
+/// Header names are matched case-sensitively, just like with
+/// [`RateLimit::new`].
+///
+/// ```
+/// use rate_limits::RateLimitBuilder;
+///
+/// let mut builder = RateLimitBuilder::new();
+/// builder.header("x-ratelimit-limit", "5000");
+/// builder.header("x-ratelimit-remaining", "4987");
+/// builder.header("x-ratelimit-reset", "1350085394");
+///
+/// let rate_limit = builder.build().unwrap();
+/// assert_eq!(rate_limit.remaining(), Some(4987));
+/// ```
+#[derive(Debug, Default)]
+pub struct RateLimitBuilder {
+    headers: CaseSensitiveHeaderMap,
+    error: Option<Error>,
+}
+
+impl RateLimitBuilder {
+    /// Create a new builder without any headers
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            headers: CaseSensitiveHeaderMap::new(),
+            error: None,
+        }
+    }
+
+    /// Add a header
+    ///
+    /// If the value is invalid, the error is reported by
+    /// [`RateLimitBuilder::build`].
+    pub fn header<V: AsRef<[u8]>>(&mut self, name: &str, value: V) -> &mut Self {
+        match HeaderValue::from_bytes(value.as_ref()) {
+            Ok(value) => {
+                self.headers.insert(name.to_string(), value);
+            }
+            Err(e) => {
+                self.error.get_or_insert(e.into());
+            }
+        }
+        self
+    }
+
+    /// Parse the rate limit from the headers added so far
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a header value was invalid or if
+    /// the rate limit cannot be parsed from the headers.
+    pub fn build(self) -> Result<RateLimit> {
+        match self.error {
+            Some(e) => Err(e),
+            None => RateLimit::from_header_map(&self.headers),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_from_headers() {
+        let mut builder = RateLimitBuilder::new();
+        builder
+            .header("x-ratelimit-limit", "5000")
+            .header("x-ratelimit-remaining", b"4987")
+            .header("x-ratelimit-reset", "1350085394");
+
+        let rate = builder.build().unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), Some(4987));
+    }
+
+    #[test]
+    fn build_with_invalid_value() {
+        let mut builder = RateLimitBuilder::default();
+        builder
+            .header("x-ratelimit-limit", "5000")
+            .header("x-ratelimit-remaining", "49\n87")
+            .header("x-ratelimit-reset", "1350085394");
+
+        assert!(matches!(builder.build(), Err(Error::InvalidHeaderValue(_))));
+    }
+
+    #[test]
+    fn build_without_headers() {
+        assert!(RateLimitBuilder::new().build().unwrap_err().is_missing());
+    }
+}
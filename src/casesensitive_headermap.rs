@@ -1,21 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{btree_map, BTreeMap};
 use std::str::FromStr;
 
 use crate::error::{Error, Result};
-use headers::{HeaderMap, HeaderName, HeaderValue};
+use headers::{HeaderMap, HeaderValue};
 
 const HEADER_SEPARATOR: &str = ":";
 
 /// A case-sensitive header map.
 ///
-/// This is a wrapper around `std::collections::HashMap` that is used to store
+/// This is a wrapper around `std::collections::BTreeMap` that is used to store
 /// HTTP headers. The difference is that this map is case-sensitive.
 ///
 /// This is required because some vendors use the same headers
 /// and the only way to differentiate them is by the case.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CaseSensitiveHeaderMap {
-    inner: HashMap<String, HeaderValue>,
+    inner: BTreeMap<String, HeaderValue>,
 }
 
 impl Default for CaseSensitiveHeaderMap {
@@ -26,9 +26,9 @@ impl Default for CaseSensitiveHeaderMap {
 
 impl CaseSensitiveHeaderMap {
     /// Create a new `CaseSensitiveHeaderMap`.
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
-            inner: HashMap::new(),
+            inner: BTreeMap::new(),
         }
     }
 
@@ -41,30 +41,119 @@ impl CaseSensitiveHeaderMap {
     pub fn get(&self, k: &str) -> Option<&HeaderValue> {
         self.inner.get(k)
     }
+
+    /// Get a header value as a string, with surrounding whitespace removed.
+    ///
+    /// Returns `None` if the header is missing.
+    ///
+    /// # Errors
+    ///
+    /// The inner result is an error if the value contains characters
+    /// which are not visible ASCII.
+    pub fn get_str(&self, k: &str) -> Option<Result<&str>> {
+        self.get(k)
+            .map(|value| Ok(value.to_str().map_err(Error::from)?.trim()))
+    }
+
+    /// Parse headers separated by newlines from raw bytes.
+    ///
+    /// See [`CaseSensitiveHeaderMap::from_str`] for details.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the bytes are not valid UTF-8
+    /// or if a header value is invalid.
+    pub fn from_bytes(headers: &[u8]) -> Result<Self> {
+        Self::from_str(std::str::from_utf8(headers)?)
+    }
+
+    /// Number of headers in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the map contains no headers.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Iterate over all headers, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &HeaderValue)> {
+        self.inner
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+impl IntoIterator for CaseSensitiveHeaderMap {
+    type Item = (String, HeaderValue);
+    type IntoIter = btree_map::IntoIter<String, HeaderValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CaseSensitiveHeaderMap {
+    type Item = (&'a String, &'a HeaderValue);
+    type IntoIter = btree_map::Iter<'a, String, HeaderValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.iter()
+    }
+}
+
+impl Extend<(String, HeaderValue)> for CaseSensitiveHeaderMap {
+    fn extend<T: IntoIterator<Item = (String, HeaderValue)>>(&mut self, iter: T) {
+        self.inner.extend(iter);
+    }
+}
+
+impl FromIterator<(String, HeaderValue)> for CaseSensitiveHeaderMap {
+    fn from_iter<T: IntoIterator<Item = (String, HeaderValue)>>(iter: T) -> Self {
+        Self {
+            inner: iter.into_iter().collect(),
+        }
+    }
 }
 
 impl FromStr for CaseSensitiveHeaderMap {
     type Err = Error;
 
+    /// Parse headers separated by newlines, e.g. `X-RateLimit-Limit: 100`.
+    /// Lines without a colon are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a header value is invalid,
+    /// e.g. because it contains control characters.
     fn from_str(headers: &str) -> Result<Self> {
-        Ok(CaseSensitiveHeaderMap {
-            inner: headers
-                .lines()
-                .filter_map(|line| line.split_once(HEADER_SEPARATOR))
-                .map(|(header, value)| {
-                    (
-                        header.to_string(),
-                        HeaderValue::from_str(value.trim()).unwrap(),
-                    )
-                })
-                .collect(),
-        })
+        headers
+            .lines()
+            .filter_map(|line| line.split_once(HEADER_SEPARATOR))
+            .map(|(header, value)| Ok((header.to_string(), HeaderValue::from_str(value.trim())?)))
+            .collect()
     }
 }
 
 impl From<&str> for CaseSensitiveHeaderMap {
+    /// Parse headers separated by newlines.
+    ///
+    /// Unlike [`CaseSensitiveHeaderMap::from_str`], this never fails.
+    /// Headers with invalid values are skipped instead.
     fn from(headers: &str) -> Self {
-        CaseSensitiveHeaderMap::from_str(headers).unwrap()
+        headers
+            .lines()
+            .filter_map(|line| line.split_once(HEADER_SEPARATOR))
+            .filter_map(|(header, value)| {
+                Some((
+                    header.to_string(),
+                    HeaderValue::from_str(value.trim()).ok()?,
+                ))
+            })
+            .collect()
     }
 }
 
@@ -88,12 +177,20 @@ impl From<&HeaderMap> for CaseSensitiveHeaderMap {
     }
 }
 
+impl From<&http::response::Parts> for CaseSensitiveHeaderMap {
+    fn from(parts: &http::response::Parts) -> Self {
+        Self::from(&parts.headers)
+    }
+}
+
 /// Extension trait for `HeaderMap` to convert from raw string.
+#[cfg(test)]
 pub(crate) trait HeaderMapExt {
     /// Convert from raw string.
     fn from_raw(raw: &str) -> Result<HeaderMap>;
 }
 
+#[cfg(test)]
 impl HeaderMapExt for HeaderMap {
     fn from_raw(raw: &str) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
@@ -104,7 +201,7 @@ impl HeaderMapExt for HeaderMap {
             }
             if let Some((name, value)) = line.split_once(HEADER_SEPARATOR) {
                 headers.insert(
-                    HeaderName::from_str(name)?,
+                    headers::HeaderName::from_str(name)?,
                     HeaderValue::from_str(value.trim())?,
                 );
             }
@@ -117,6 +214,69 @@ impl HeaderMapExt for HeaderMap {
 mod tests {
     use super::*;
 
+    #[test]
+    fn build_and_iterate() {
+        let mut map = CaseSensitiveHeaderMap::new();
+        assert!(map.is_empty());
+
+        map.insert(
+            "X-RateLimit-Limit".to_string(),
+            HeaderValue::from_static("100"),
+        );
+        map.extend([(
+            "X-RateLimit-Remaining".to_string(),
+            HeaderValue::from_static("99"),
+        )]);
+        assert_eq!(map.len(), 2);
+
+        let mut names: Vec<&str> = map.iter().map(|(name, _)| name).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["X-RateLimit-Limit", "X-RateLimit-Remaining"]);
+
+        let collected: CaseSensitiveHeaderMap = map.clone().into_iter().collect();
+        assert_eq!(collected, map);
+    }
+
+    #[test]
+    fn get_str() {
+        let mut map = CaseSensitiveHeaderMap::new();
+        map.insert(
+            "X-RateLimit-Limit".to_string(),
+            HeaderValue::from_static(" 100 "),
+        );
+        map.insert(
+            "X-RateLimit-Remaining".to_string(),
+            HeaderValue::from_bytes(b"9\xff").unwrap(),
+        );
+        assert_eq!(map.get_str("X-RateLimit-Limit").unwrap().unwrap(), "100");
+        assert!(map.get_str("X-RateLimit-Remaining").unwrap().is_err());
+        assert!(map.get_str("x-ratelimit-limit").is_none());
+    }
+
+    #[test]
+    fn from_bytes() {
+        let map = CaseSensitiveHeaderMap::from_bytes(b"X-RateLimit-Limit: 100\r\n").unwrap();
+        assert_eq!(map.get_str("X-RateLimit-Limit").unwrap().unwrap(), "100");
+
+        assert!(matches!(
+            CaseSensitiveHeaderMap::from_bytes(b"X-RateLimit-Limit: 1\xff0"),
+            Err(Error::Utf8(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_header_value() {
+        let headers = "X-RateLimit-Limit: 100\nX-RateLimit-Remaining: 9\u{7f}9";
+        assert!(matches!(
+            CaseSensitiveHeaderMap::from_str(headers),
+            Err(Error::InvalidHeaderValue(_))
+        ));
+
+        let map = CaseSensitiveHeaderMap::from(headers);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("X-RateLimit-Limit").unwrap(), "100");
+    }
+
     #[test]
     fn test_convert_from_header_map() {
         let mut headers = HeaderMap::new();
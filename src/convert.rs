@@ -1,9 +1,399 @@
-use crate::error::Result;
+use std::borrow::Cow;
+
+use headers::HeaderValue;
+
+use crate::error::{Error, Result};
+
+/// Separator used by proxies when coalescing duplicate headers into one
+const LIST_SEPARATOR: char = ',';
+
+/// Get the value to parse from a header value.
+///
+/// Proxies may coalesce duplicate headers into a single comma-separated
+/// value (e.g. `10, 5`). In that case, the last element is used, because it
+/// is typically the one from the innermost (most recent) hop.
+///
+/// Some servers quote values (e.g. `"42"`), like strings in structured
+/// header fields. Surrounding double quotes are stripped.
+pub(crate) fn last_value(value: &str) -> &str {
+    unquote(value.rsplit(LIST_SEPARATOR).next().unwrap_or(value))
+}
+
+/// Trim a header value and strip surrounding double quotes
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Separators used to group the digits of large numbers (e.g. `1,000`)
+const THOUSANDS_SEPARATORS: [char; 2] = [',', '_'];
+
+/// Check if the value is a number with thousands separators which can't be
+/// a list, e.g. `1,000` or `1_000_000`, optionally with a fraction.
+///
+/// The value must not contain whitespace, only one kind of separator may be
+/// used, the first group must have one to three digits without a leading
+/// zero and all other groups exactly three digits.
+fn is_grouped(value: &str) -> bool {
+    let digits = |group: &str| group.bytes().all(|b| b.is_ascii_digit());
+    let integer = value.split_once('.').map_or(value, |(integer, _)| integer);
+    THOUSANDS_SEPARATORS.iter().any(|&separator| {
+        let mut groups = integer.split(separator);
+        let first = groups.next().unwrap_or_default();
+        let mut rest = groups.peekable();
+        rest.peek().is_some()
+            && (1..=3).contains(&first.len())
+            && digits(first)
+            && !first.starts_with('0')
+            && rest.all(|group| group.len() == 3 && digits(group))
+    })
+}
+
+/// Get the numeric value to parse from a header value.
+///
+/// Like [`last_value`], but numbers with thousands separators (`,` or `_`),
+/// which some locale-configured servers send, are read as a whole with the
+/// separators stripped (see [`is_grouped`]). So `1,000` and `10,500` are
+/// single numbers, while `10,5`, `1, 000` and `010,500` are lists, of which
+/// the last element is used.
+fn number_value(value: &str) -> Cow<'_, str> {
+    let unquoted = unquote(value);
+    if is_grouped(unquoted) {
+        Cow::Owned(unquoted.replace(THOUSANDS_SEPARATORS, ""))
+    } else {
+        Cow::Borrowed(last_value(value))
+    }
+}
+
+/// Parse a header value with the given parser.
+///
+/// Errors are mapped to [`Error::InvalidValueFor`], which contains the header
+/// name and the offending value. Errors about missing fields (e.g. of a
+/// structured header) are kept, so that [`Error::is_missing`] works.
+pub(crate) fn parse_header<T>(
+    name: &str,
+    value: &HeaderValue,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Result<T> {
+    value
+        .to_str()
+        .map_err(Error::from)
+        .and_then(parse)
+        .map_err(|e| {
+            if e.is_missing() {
+                e
+            } else {
+                Error::InvalidValueFor {
+                    header: name.to_string(),
+                    value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                }
+            }
+        })
+}
 
 pub(crate) fn to_usize(value: &str) -> Result<usize> {
-    Ok(value.trim().parse::<usize>()?)
+    Ok(number_value(value).parse::<usize>()?)
+}
+
+/// Parse a request count, which may be fractional for token bucket APIs
+/// (e.g. `4.5` remaining tokens).
+///
+/// Fractional values are rounded down, because a partial token
+/// does not allow for another request.
+pub(crate) fn to_usize_floor(value: &str) -> Result<usize> {
+    let value = number_value(value);
+    match value.parse::<usize>() {
+        Ok(count) => Ok(count),
+        Err(e) => match value.parse::<f64>() {
+            Ok(count) if count.is_finite() && count >= 0.0 => Ok(count.floor() as usize),
+            _ => Err(e.into()),
+        },
+    }
+}
+
+/// Parse a number of seconds, which may be fractional (e.g. `2.5`).
+///
+/// Fractional values are always rounded *up* to the next whole second.
+/// Rounding down would make clients retry before the server is ready.
+/// Negative and non-finite values are rejected.
+pub(crate) fn to_seconds_ceil(value: &str) -> Result<usize> {
+    let value = number_value(value);
+    match value.parse::<usize>() {
+        Ok(seconds) => Ok(seconds),
+        Err(e) => match value.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds.ceil() as usize),
+            _ => Err(e.into()),
+        },
+    }
+}
+
+/// Parse a percentage between 0 and 100, which may be fractional.
+pub(crate) fn to_percent(value: &str) -> Result<f64> {
+    let value = number_value(value);
+    match value.parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(Error::InvalidPercentage(value.to_string())),
+    }
+}
+
+/// Parse a boolean (`true` or `false`), ignoring case.
+pub(crate) fn to_bool(value: &str) -> Result<bool> {
+    let value = last_value(value);
+    if value.eq_ignore_ascii_case("true") {
+        Ok(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Ok(false)
+    } else {
+        Err(Error::InvalidBool(value.to_string()))
+    }
+}
+
+/// Nanoseconds per unit of a Go-style duration string
+const GO_DURATION_UNITS: &[(&str, u128)] = &[
+    ("ns", 1),
+    ("us", 1_000),
+    ("\u{b5}s", 1_000),  // U+00B5 micro sign
+    ("\u{3bc}s", 1_000), // U+03BC greek letter mu
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+];
+
+/// Parse a Go-style duration string (e.g. `6m0s`, `1.5s` or `20ms`)
+/// into seconds.
+///
+/// A duration is a sequence of decimal numbers, each with an optional
+/// fraction and a unit suffix (`ns`, `us`/`µs`, `ms`, `s`, `m` or `h`).
+/// A plain `0` is accepted as well. Like with [`to_seconds_ceil`], the
+/// result is rounded up to the next whole second, so any duration
+/// shorter than a second (but not zero) becomes one second.
+pub(crate) fn go_duration_seconds(value: &str) -> Result<usize> {
+    let value = last_value(value);
+    let invalid = || Error::InvalidDuration(value.to_string());
+    if value == "0" {
+        return Ok(0);
+    }
+    if value.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut nanos: u128 = 0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let (number, tail) = rest.split_at(
+            rest.find(|c: char| !c.is_ascii_digit() && c != '.')
+                .ok_or_else(invalid)?,
+        );
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+            return Err(invalid());
+        }
+
+        let (unit, tail) = tail.split_at(
+            tail.find(|c: char| c.is_ascii_digit() || c == '.')
+                .unwrap_or(tail.len()),
+        );
+        let &(_, factor) = GO_DURATION_UNITS
+            .iter()
+            .find(|&&(name, _)| name == unit)
+            .ok_or_else(invalid)?;
+
+        let whole = if whole.is_empty() {
+            0
+        } else {
+            whole.parse::<u128>().map_err(|_| invalid())?
+        };
+        let mut scale = factor;
+        let mut fraction_nanos = 0;
+        for digit in fraction.bytes().map(|b| u128::from(b - b'0')) {
+            scale /= 10;
+            if scale == 0 {
+                // Digits beyond nanosecond precision round up to the next
+                // nanosecond, so that no non-zero duration becomes zero
+                if digit > 0 {
+                    fraction_nanos += 1;
+                    break;
+                }
+            } else {
+                fraction_nanos += digit * scale;
+            }
+        }
+
+        nanos = whole
+            .checked_mul(factor)
+            .and_then(|n| n.checked_add(fraction_nanos))
+            .and_then(|n| n.checked_add(nanos))
+            .ok_or_else(invalid)?;
+        rest = tail;
+    }
+
+    usize::try_from(nanos.div_ceil(1_000_000_000)).map_err(|_| invalid())
 }
 
-pub(crate) fn to_i64(value: &str) -> Result<i64> {
-    Ok(value.trim().parse::<i64>()?)
+/// Parse a Unix timestamp, which may be fractional (e.g. `1470173023.123`).
+///
+/// Like with [`to_seconds_ceil`], fractional values are rounded up.
+pub(crate) fn to_timestamp_ceil(value: &str) -> Result<i64> {
+    let value = number_value(value);
+    match value.parse::<i64>() {
+        Ok(timestamp) => Ok(timestamp),
+        Err(e) => match value.parse::<f64>() {
+            Ok(timestamp) if timestamp.is_finite() => Ok(timestamp.ceil() as i64),
+            _ => Err(e.into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_single_value() {
+        assert_eq!(to_usize(" 10 ").unwrap(), 10);
+        assert_eq!(to_timestamp_ceil("1350085394").unwrap(), 1_350_085_394);
+    }
+
+    #[test]
+    fn parse_comma_separated_values() {
+        assert_eq!(to_usize("10, 5").unwrap(), 5);
+        assert_eq!(to_usize("10,5").unwrap(), 5);
+        assert_eq!(
+            to_timestamp_ceil("1350085394, 1350085395").unwrap(),
+            1_350_085_395
+        );
+    }
+
+    #[test]
+    fn parse_thousands_separators() {
+        assert_eq!(to_usize("1,000").unwrap(), 1000);
+        assert_eq!(to_usize("1_000").unwrap(), 1000);
+        assert_eq!(to_usize("1,000,000").unwrap(), 1_000_000);
+        assert_eq!(to_usize("\"1,000\"").unwrap(), 1000);
+        assert_eq!(to_usize_floor("1,000.5").unwrap(), 1000);
+        assert_eq!(to_seconds_ceil("3_600").unwrap(), 3600);
+
+        assert_eq!(to_usize("10,500").unwrap(), 10500);
+        assert_eq!(to_timestamp_ceil("1,350,085,394").unwrap(), 1_350_085_394);
+
+        // Values which can't be grouped numbers are lists
+        assert_eq!(to_usize("1, 000").unwrap(), 0);
+        assert_eq!(to_usize("10,5").unwrap(), 5);
+        assert_eq!(to_usize("1000,5").unwrap(), 5);
+        assert_eq!(to_usize("010,500").unwrap(), 500);
+        assert_eq!(to_percent("10,50").unwrap(), 50.0);
+        assert!(to_usize("1_0").is_err());
+        assert!(to_usize("1,000_000").is_err());
+    }
+
+    #[test]
+    fn parse_quoted_values() {
+        assert_eq!(to_usize("\"42\"").unwrap(), 42);
+        assert_eq!(to_usize(" \"42\" ").unwrap(), 42);
+        assert_eq!(to_usize("\"10\", \"5\"").unwrap(), 5);
+        assert_eq!(to_seconds_ceil("\"2.5\"").unwrap(), 3);
+        assert_eq!(go_duration_seconds("\"1s\"").unwrap(), 1);
+        assert!(to_usize("\"42").is_err());
+        assert!(to_usize("\"\"").is_err());
+    }
+
+    #[test]
+    fn parse_fractional_count() {
+        assert_eq!(to_usize_floor("4.5").unwrap(), 4);
+        assert_eq!(to_usize_floor("0.0").unwrap(), 0);
+        assert_eq!(to_usize_floor("42").unwrap(), 42);
+        assert!(to_usize_floor("-1.5").is_err());
+        assert!(to_usize_floor("NaN").is_err());
+        assert!(to_usize_floor("inf").is_err());
+        assert!(to_usize("4.5").is_err());
+    }
+
+    #[test]
+    fn parse_fractional_seconds() {
+        assert_eq!(to_seconds_ceil("2.5").unwrap(), 3);
+        assert_eq!(to_seconds_ceil("0.001").unwrap(), 1);
+        assert_eq!(to_seconds_ceil("0.0").unwrap(), 0);
+        assert_eq!(to_seconds_ceil("3.0").unwrap(), 3);
+        assert_eq!(to_seconds_ceil("42").unwrap(), 42);
+        assert!(to_seconds_ceil("-0.5").is_err());
+        assert!(to_seconds_ceil("NaN").is_err());
+    }
+
+    #[test]
+    fn parse_fractional_timestamp() {
+        assert_eq!(to_timestamp_ceil("1470173023").unwrap(), 1_470_173_023);
+        assert_eq!(to_timestamp_ceil("1470173023.123").unwrap(), 1_470_173_024);
+        assert!(to_timestamp_ceil("NaN").is_err());
+    }
+
+    #[test]
+    fn parse_percent() {
+        assert_eq!(to_percent("12.5").unwrap(), 12.5);
+        assert_eq!(to_percent("0").unwrap(), 0.0);
+        assert_eq!(to_percent("100").unwrap(), 100.0);
+        assert!(to_percent("100.1").is_err());
+        assert!(to_percent("-1").is_err());
+        assert!(to_percent("NaN").is_err());
+    }
+
+    #[test]
+    fn parse_bool() {
+        assert!(to_bool("true").unwrap());
+        assert!(to_bool("True").unwrap());
+        assert!(!to_bool("false").unwrap());
+        assert!(to_bool("1").is_err());
+        assert!(to_bool("yes").is_err());
+    }
+
+    #[test]
+    fn parse_go_duration() {
+        assert_eq!(go_duration_seconds("6m0s").unwrap(), 360);
+        assert_eq!(go_duration_seconds("1.5s").unwrap(), 2);
+        assert_eq!(go_duration_seconds("1h30m").unwrap(), 5400);
+        assert_eq!(go_duration_seconds("1h0m0s").unwrap(), 3600);
+        assert_eq!(go_duration_seconds("2m30.5s").unwrap(), 151);
+        assert_eq!(go_duration_seconds(".5s").unwrap(), 1);
+        assert_eq!(go_duration_seconds("1.s").unwrap(), 1);
+    }
+
+    #[test]
+    fn parse_go_duration_sub_second() {
+        assert_eq!(go_duration_seconds("20ms").unwrap(), 1);
+        assert_eq!(go_duration_seconds("1500ms").unwrap(), 2);
+        assert_eq!(go_duration_seconds("2000ms").unwrap(), 2);
+        assert_eq!(go_duration_seconds("300us").unwrap(), 1);
+        assert_eq!(go_duration_seconds("300\u{b5}s").unwrap(), 1);
+        assert_eq!(go_duration_seconds("300\u{3bc}s").unwrap(), 1);
+        assert_eq!(go_duration_seconds("1ns").unwrap(), 1);
+        assert_eq!(go_duration_seconds("1s500ms").unwrap(), 2);
+        assert_eq!(go_duration_seconds("0.000000000001s").unwrap(), 1);
+        assert_eq!(go_duration_seconds("0.1ns").unwrap(), 1);
+        assert_eq!(go_duration_seconds("0.0000000000s").unwrap(), 0);
+    }
+
+    #[test]
+    fn parse_go_duration_zero() {
+        assert_eq!(go_duration_seconds("0").unwrap(), 0);
+        assert_eq!(go_duration_seconds("0s").unwrap(), 0);
+        assert_eq!(go_duration_seconds("0m0s").unwrap(), 0);
+        assert_eq!(go_duration_seconds("0.0ms").unwrap(), 0);
+    }
+
+    #[test]
+    fn parse_invalid_go_duration() {
+        for value in ["", "10", "5x", "s", ".s", "1.2.3s", "-1s", "1s 2s", "1S"] {
+            assert!(go_duration_seconds(value).is_err(), "{value}");
+        }
+        assert!(go_duration_seconds("99999999999999999999999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn parse_invalid_list() {
+        assert!(to_usize("10, foo").is_err());
+        assert!(to_usize("10,").is_err());
+    }
 }
@@ -6,24 +6,48 @@ use thiserror::Error;
 /// Error variants while parsing the rate limit headers
 #[derive(Display, Debug, Error)]
 pub enum Error {
-    /// HTTP x-ratelimit-limit header not found
-    MissingLimit,
-
-    /// HTTP x-ratelimit-used  header not found
-    MissingUsed,
-
-    /// HTTP x-ratelimit-remaining header not found
-    MissingRemaining,
-
-    /// HTTP x-ratelimit-reset header not found
-    MissingReset,
-
-    /// HTTP Retry-After header not found
-    MissingRetryAfter,
+    /// HTTP x-ratelimit-limit header not found (checked {candidates:?})
+    MissingLimit {
+        /// Header names which were checked
+        candidates: Vec<String>,
+    },
+
+    /// HTTP x-ratelimit-used header not found (checked {candidates:?})
+    MissingUsed {
+        /// Header names which were checked
+        candidates: Vec<String>,
+    },
+
+    /// HTTP x-ratelimit-remaining header not found (checked {candidates:?})
+    MissingRemaining {
+        /// Header names which were checked
+        candidates: Vec<String>,
+    },
+
+    /// HTTP x-ratelimit-reset header not found (checked {candidates:?})
+    MissingReset {
+        /// Header names which were checked
+        candidates: Vec<String>,
+    },
+
+    /// HTTP Retry-After header not found (checked {candidates:?})
+    MissingRetryAfter {
+        /// Header names which were checked
+        candidates: Vec<String>,
+    },
 
     /// Invalid Retry-After header value
     InvalidRetryAfter(String),
 
+    /// Unknown vendor: {0}
+    UnknownVendor(String),
+
+    /// Headers match more than one vendor: {vendors:?}
+    AmbiguousVendor {
+        /// Vendors whose headers are all present
+        vendors: Vec<crate::Vendor>,
+    },
+
     /// Header does not contain colon
     HeaderWithoutColon(String),
 
@@ -36,10 +60,31 @@ pub enum Error {
     /// Cannot convert header value to string
     ToStr(#[from] http::header::ToStrError),
 
+    /// Headers are not valid UTF-8: {0}
+    Utf8(#[from] std::str::Utf8Error),
+
     /// Cannot parse rate limit header value: {0}
     InvalidValue(#[from] ParseIntError),
 
+    /// Invalid duration: {0}
+    InvalidDuration(String),
+
+    /// Invalid percentage: {0}
+    InvalidPercentage(String),
+
+    /// Invalid boolean: {0}
+    InvalidBool(String),
+
+    /// Invalid value {value:?} for header {header}
+    InvalidValueFor {
+        /// Name of the header
+        header: String,
+        /// Offending header value
+        value: String,
+    },
+
     /// Cannot lock header map
+    #[deprecated(note = "never returned, header maps are not locked")]
     Lock,
 
     /// Time Parsing error
@@ -49,4 +94,23 @@ pub enum Error {
     Time(#[from] time::error::ComponentRange),
 }
 
+impl Error {
+    /// Check if the error is caused by missing headers
+    ///
+    /// This is the case if a response does not contain rate limit headers,
+    /// which is normal for APIs without rate limits. All other errors are
+    /// caused by invalid header values.
+    #[must_use]
+    pub const fn is_missing(&self) -> bool {
+        matches!(
+            self,
+            Error::MissingLimit { .. }
+                | Error::MissingUsed { .. }
+                | Error::MissingRemaining { .. }
+                | Error::MissingReset { .. }
+                | Error::MissingRetryAfter { .. }
+        )
+    }
+}
+
 pub(crate) type Result<T> = std::result::Result<T, Error>;
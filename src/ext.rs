@@ -0,0 +1,100 @@
+//! Extension traits for parsing rate limits directly from HTTP responses.
+use crate::error::Error;
+use crate::RateLimit;
+
+/// Extension trait to parse a [`RateLimit`] from an HTTP response
+///
+/// This is implemented for the response types of supported HTTP clients,
+/// so that rate limit information can be read with
+/// `response.rate_limit()`.
+///
+/// The header maps of HTTP clients lowercase header names, so they are
+/// matched regardless of case (see [`RateLimit::new_case_insensitive`]).
+pub trait RateLimitExt {
+    /// Parse the rate limit information from the response headers.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the response does not contain
+    /// rate limit headers or if the header values cannot be parsed.
+    fn rate_limit(&self) -> Result<RateLimit, Error>;
+}
+
+#[cfg(feature = "reqwest")]
+impl RateLimitExt for reqwest::Response {
+    fn rate_limit(&self) -> Result<RateLimit, Error> {
+        RateLimit::new_case_insensitive(self.headers())
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl RateLimitExt for &reqwest::Response {
+    fn rate_limit(&self) -> Result<RateLimit, Error> {
+        RateLimit::new_case_insensitive(self.headers())
+    }
+}
+
+impl<T> RateLimitExt for http::Response<T> {
+    fn rate_limit(&self) -> Result<RateLimit, Error> {
+        RateLimit::new_case_insensitive(self.headers())
+    }
+}
+
+impl RateLimitExt for http::response::Parts {
+    fn rate_limit(&self) -> Result<RateLimit, Error> {
+        RateLimit::new_case_insensitive(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_http_response() {
+        let response = http::Response::builder()
+            .header("x-ratelimit-limit", "5000")
+            .header("x-ratelimit-remaining", "4987")
+            .header("x-ratelimit-reset", "1350085394")
+            .body(())
+            .unwrap();
+
+        let rate = response.rate_limit().unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), Some(4987));
+    }
+
+    #[test]
+    fn parse_response_parts() {
+        let (parts, _) = http::Response::builder()
+            .header("x-ratelimit-limit", "5000")
+            .header("x-ratelimit-remaining", "4987")
+            .header("x-ratelimit-reset", "1350085394")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        assert_eq!(parts.rate_limit().unwrap(), RateLimit::new(&parts).unwrap());
+        assert_eq!(RateLimit::new(&parts).unwrap().remaining(), Some(4987));
+    }
+
+    #[test]
+    fn parse_lowercased_response() {
+        // Vimeo's headers are only recognized regardless of case
+        let (parts, ()) = http::Response::builder()
+            .header("X-RateLimit-Limit", "60")
+            .header("X-RateLimit-Remaining", "59")
+            .header("X-RateLimit-Reset", "Tue, 15 Nov 1994 08:12:31 GMT")
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert!(RateLimit::new(&parts).is_err());
+        assert_eq!(
+            parts.rate_limit().unwrap().vendor(),
+            Some(crate::Vendor::Vimeo)
+        );
+
+        let response = http::Response::from_parts(parts, ());
+        assert_eq!(response.rate_limit().unwrap().remaining(), Some(59));
+    }
+}
@@ -0,0 +1,84 @@
+//! Extractor for reading rate limits in [axum] handlers
+//!
+//! [axum]: https://docs.rs/axum
+use async_trait::async_trait;
+use axum_core::extract::FromRequestParts;
+use http::{request::Parts, StatusCode};
+
+use crate::RateLimit;
+
+/// Extract the rate limit from the request headers
+///
+/// This is useful for proxies and API gateways which forward the rate limits
+/// of an upstream service. Requests without valid rate limit headers are
+/// rejected with `400 Bad Request`. Use `Option<RateLimit>` to accept them
+/// anyway.
+///
+/// Header names are matched regardless of case, because `http::HeaderMap`
+/// lowercases them (see [`RateLimit::new_case_insensitive`]).
+#[async_trait]
+impl<S> FromRequestParts<S> for RateLimit
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        RateLimit::new_case_insensitive(&parts.headers)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    /// Poll a future which is ready immediately
+    fn now_or_never<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is not ready"),
+        }
+    }
+
+    #[test]
+    fn extract_rate_limit() {
+        let (mut parts, ()) = http::Request::builder()
+            .header("x-ratelimit-limit", "5000")
+            .header("x-ratelimit-remaining", "4987")
+            .header("x-ratelimit-reset", "1350085394")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        let rate = now_or_never(RateLimit::from_request_parts(&mut parts, &())).unwrap();
+        assert_eq!(rate.remaining(), Some(4987));
+    }
+
+    #[test]
+    fn extract_lowercased_rate_limit() {
+        // Vimeo's headers are only recognized regardless of case
+        let (mut parts, ()) = http::Request::builder()
+            .header("X-RateLimit-Limit", "60")
+            .header("X-RateLimit-Remaining", "59")
+            .header("X-RateLimit-Reset", "Tue, 15 Nov 1994 08:12:31 GMT")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        let rate = now_or_never(RateLimit::from_request_parts(&mut parts, &())).unwrap();
+        assert_eq!(rate.vendor(), Some(crate::Vendor::Vimeo));
+        assert_eq!(rate.remaining(), Some(59));
+    }
+
+    #[test]
+    fn reject_missing_rate_limit() {
+        let (mut parts, ()) = http::Request::builder().body(()).unwrap().into_parts();
+
+        let (status, _) = now_or_never(RateLimit::from_request_parts(&mut parts, &())).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,151 @@
+//! Heuristic fallback for rate limit headers of unknown vendors
+//!
+//! Many APIs send headers like `Foo-RateLimit-Remaining` and
+//! `Foo-RateLimit-Reset`, which don't belong to any known vendor. If none of
+//! the known variants match, the headers are guessed from their names. This is
+//! a best-effort heuristic: the format of the values is sniffed and the result
+//! may be wrong.
+use std::borrow::Cow;
+
+use headers::HeaderValue;
+
+use crate::casesensitive_headermap::CaseSensitiveHeaderMap;
+use crate::convert;
+use crate::error::Result;
+use crate::reset_time::{ResetTime, ResetTimeKind};
+
+use super::{Headers, Limit, Remaining, Vendor};
+
+/// Suffixes of the remaining requests header, in lowercase
+///
+/// The prefix before the suffix (e.g. `x-` or `foo-`) is shared by all
+/// headers of a vendor.
+const REMAINING_SUFFIXES: [&str; 2] = ["ratelimit-remaining", "rate-limit-remaining"];
+
+/// Find a header whose name is `prefix` followed by `suffix`,
+/// ignoring the case of both
+fn find<'a>(
+    header_map: &'a CaseSensitiveHeaderMap,
+    prefix: &str,
+    suffix: &str,
+) -> Option<(&'a str, &'a HeaderValue)> {
+    header_map.iter().find(|(name, _)| {
+        name.len() == prefix.len() + suffix.len()
+            && name.is_char_boundary(prefix.len())
+            && name[..prefix.len()].eq_ignore_ascii_case(prefix)
+            && name[prefix.len()..].eq_ignore_ascii_case(suffix)
+    })
+}
+
+/// Guess rate limits from headers ending in `ratelimit-remaining` and
+/// `ratelimit-reset` (or `rate-limit-...`), ignoring case,
+/// or `None` if there are no such headers
+///
+/// The limit is read from a matching `...-limit` header if present. The reset
+/// time is sniffed (see [`ResetTimeKind::Sniff`]). The result is tagged as
+/// [`Vendor::Standard`]. The matched header names are copied from the header
+/// map, because they are not known in advance.
+pub(crate) fn parse(header_map: &CaseSensitiveHeaderMap) -> Option<Result<Headers>> {
+    header_map.iter().find_map(|(name, remaining)| {
+        let lowercase = name.to_ascii_lowercase();
+        let suffix = REMAINING_SUFFIXES
+            .iter()
+            .find(|suffix| lowercase.ends_with(*suffix))?;
+        let prefix = &name[..name.len() - suffix.len()];
+        let base = &suffix[..suffix.len() - "remaining".len()];
+        let (reset_name, reset) = find(header_map, prefix, &format!("{base}reset"))?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            remaining = name,
+            reset = reset_name,
+            "guessing rate limits from unknown headers"
+        );
+
+        Some(build(
+            header_map,
+            (prefix, base),
+            (name, remaining),
+            (reset_name, reset),
+        ))
+    })
+}
+
+/// Parse the headers found by [`parse`]
+fn build(
+    header_map: &CaseSensitiveHeaderMap,
+    (prefix, base): (&str, &str),
+    (remaining_name, remaining): (&str, &HeaderValue),
+    (reset_name, reset): (&str, &HeaderValue),
+) -> Result<Headers> {
+    let mut matched_headers = vec![remaining_name];
+    let limit = find(header_map, prefix, &format!("{base}limit"))
+        .map(|(name, value)| {
+            matched_headers.push(name);
+            convert::parse_header(name, value, |value| Limit::new(value))
+        })
+        .transpose()?;
+    matched_headers.push(reset_name);
+    Ok(Headers {
+        limit: limit.map(|limit| limit.count),
+        remaining: convert::parse_header(remaining_name, remaining, Remaining::new)?.count,
+        used: None,
+        reset: convert::parse_header(reset_name, reset, |value| {
+            ResetTime::parse(value, ResetTimeKind::Sniff)
+        })?,
+        window: None,
+        vendor: Vendor::Standard,
+        reset_after: None,
+        throttle_scope: None,
+        resource: None,
+        near_limit: None,
+        matched_headers: matched_headers
+            .into_iter()
+            .map(|name| Cow::Owned(name.to_string()))
+            .collect(),
+        other_limits: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse_unknown_headers() {
+        let headers = CaseSensitiveHeaderMap::from_str(
+            "Acme-RateLimit-Limit: 100\nAcme-RateLimit-Remaining: 42\nacme-ratelimit-reset: 30",
+        )
+        .unwrap();
+        let headers = parse(&headers).unwrap().unwrap();
+        assert_eq!(headers.limit, Some(100));
+        assert_eq!(headers.remaining, 42);
+        assert_eq!(headers.reset, ResetTime::Seconds(30));
+        assert_eq!(headers.vendor, Vendor::Standard);
+        assert_eq!(
+            headers.matched_headers,
+            vec![
+                "Acme-RateLimit-Remaining",
+                "Acme-RateLimit-Limit",
+                "acme-ratelimit-reset"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_unknown_headers_with_different_prefixes() {
+        // The reset header must have the same prefix as the remaining header
+        let headers = CaseSensitiveHeaderMap::from_str(
+            "Acme-Rate-Limit-Remaining: 42\nOther-Rate-Limit-Reset: 30",
+        )
+        .unwrap();
+        assert!(parse(&headers).is_none());
+
+        let headers = CaseSensitiveHeaderMap::from_str(
+            "Acme-Rate-Limit-Remaining: 42\nAcme-Rate-Limit-Reset: soon",
+        )
+        .unwrap();
+        assert!(parse(&headers).unwrap().is_err());
+    }
+}
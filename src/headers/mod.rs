@@ -1,39 +1,93 @@
 //! Rate limit headers as defined in [RFC 6585](https://tools.ietf.org/html/rfc6585)
 //! and [draft-polli-ratelimit-headers-00][draft].
+mod generic;
+mod structured;
+mod typed;
 mod types;
 mod variants;
 
+use std::borrow::Cow;
 use std::str::FromStr;
 
 use crate::{
     casesensitive_headermap::CaseSensitiveHeaderMap,
+    convert,
+    named_limit::NamedLimit,
     reset_time::{ResetTime, ResetTimeKind},
 };
 
 use super::error::{Error, Result};
 use headers::HeaderValue;
-use variants::RATE_LIMIT_HEADERS;
+use variants::{RATE_LIMIT_HEADERS, TOKEN_LIMIT_HEADERS, VENDOR_HINT_HEADERS};
 
-use time::Duration;
+use time::{Duration, OffsetDateTime};
+pub use typed::RateLimitHeader;
 use types::Used;
-pub use types::Vendor;
-pub(crate) use types::{Limit, RateLimitVariant, Remaining};
+pub(crate) use types::{Limit, Remaining};
+pub use types::{RateLimitVariant, Vendor};
+
+/// Iterate over all known rate limit header variants
+///
+/// The variants are returned in the order in which they are checked
+/// while parsing, followed by the variants which are only used with a
+/// vendor hint (see [`Headers::new_with_vendor`]), because their headers
+/// can't be told apart from those of another vendor.
+pub fn known_variants() -> impl Iterator<Item = &'static RateLimitVariant> {
+    RATE_LIMIT_HEADERS.iter().chain(VENDOR_HINT_HEADERS)
+}
+
+/// Check if all names are contained in `other`
+fn is_subset(names: &[&str], other: &[&str]) -> bool {
+    names.iter().all(|name| other.contains(name))
+}
 
 /// HTTP rate limits as parsed from header values
-#[derive(Copy, Clone, Debug, PartialEq)]
+///
+/// New fields are added as more vendors are supported, so this can't be
+/// built with a struct literal outside of this crate. Use the parsing
+/// functions, like [`Headers::new`], instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Headers {
-    /// The maximum number of requests allowed in the time window
-    pub limit: usize,
+    /// The maximum number of requests allowed in the time window.
+    /// It is optional, because token bucket APIs don't provide a limit.
+    pub limit: Option<usize>,
     /// The number of requests remaining in the time window
     pub remaining: usize,
+    /// The number of requests used in the time window, if the vendor sends it
+    /// (e.g. Reddit's `X-Ratelimit-Used` or Gitlab's `RateLimit-Observed`).
+    /// It can exceed the limit if the client already went over it.
+    pub used: Option<usize>,
     /// The time at which the rate limit will be reset
     pub reset: ResetTime,
     /// The time window until the rate limit is lifted.
+    /// It is read from a header if the vendor sends one, otherwise the
+    /// vendor's documented window is used.
     /// It is optional, because it might not be given,
     /// in which case it needs to be inferred from the environment
     pub window: Option<Duration>,
     /// Predicted vendor based on rate limit header
     pub vendor: Vendor,
+    /// The time until the rate limit is reset, if sent in addition to an
+    /// absolute reset time (e.g. `X-RateLimit-Reset-After`)
+    pub reset_after: Option<Duration>,
+    /// The scope of the rate limit (e.g. `Tenant_Application/ReadWrite`),
+    /// if the vendor sends it
+    pub throttle_scope: Option<String>,
+    /// The resource which the rate limit applies to (e.g. `core` or
+    /// `search`), if the vendor sends it
+    pub resource: Option<String>,
+    /// Whether the vendor signals that the client is close to the limit
+    /// (e.g. Bitbucket's `X-RateLimit-NearLimit`), if it sends it
+    pub near_limit: Option<bool>,
+    /// Names of the headers which were used to parse the rate limits
+    ///
+    /// They are borrowed for known headers and owned for headers which were
+    /// guessed from their names (see [`Headers::new`]).
+    pub matched_headers: Vec<Cow<'static, str>>,
+    /// Rate limits of other dimensions than requests, which some vendors
+    /// send along (e.g. OpenAI's limit on `tokens`)
+    pub other_limits: Vec<NamedLimit>,
 }
 
 impl Headers {
@@ -44,100 +98,655 @@ impl Headers {
     /// Github, Vimeo, Twitter, Imgur, etc have their own headers.
     /// Without additional context, the parsing is done on a best-effort basis.
     ///
+    /// If no known headers are found, headers ending in `ratelimit-remaining`
+    /// and `ratelimit-reset` (e.g. `Acme-RateLimit-Remaining`) are used as a
+    /// last resort, regardless of case. This is a heuristic: the reset time
+    /// format is guessed and the vendor is reported as [`Vendor::Standard`].
+    ///
     /// # Errors
     ///
     /// This function returns an error if the given header map does not contain
     /// all required headers or if the header values cannot be parsed.
     pub fn new<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
-        let headers = headers.into();
-        let value = Self::get_remaining(&headers)?;
-        let remaining = Remaining::new(value.to_str()?)?;
-
-        let (limit, variant) = if let Ok((limit, variant)) = Self::get_rate_limit(&headers) {
-            (Limit::new(limit.to_str()?)?, variant)
-        } else if let Ok((used, variant)) = Self::get_used(&headers) {
-            // The site provides a `used` header, but no `limit` header.
-            // Therefore we have to calculate the limit from used and remaining.
-            let used = Used::new(used.to_str()?)?;
-            let limit = used.count + remaining.count;
-            (Limit::from(limit), variant)
+        Self::from_header_map(&headers.into())
+    }
+
+    /// Extracts rate limits from a borrowed header map.
+    ///
+    /// See [`Headers::new`] for details.
+    pub(crate) fn from_header_map(headers: &CaseSensitiveHeaderMap) -> Result<Self> {
+        match Self::from_variants(headers, RATE_LIMIT_HEADERS) {
+            Err(e) if e.is_missing() => Self::from_structured_header(headers)
+                .or_else(|| generic::parse(headers))
+                .unwrap_or(Err(e)),
+            result => result,
+        }
+    }
+
+    /// Extracts rate limits from a structured field dictionary, e.g.
+    /// `limit=100, remaining=42, reset=30`.
+    ///
+    /// This is the value of the combined `RateLimit` header of newer drafts
+    /// of the rate limit headers standard. The `remaining` and `reset` keys
+    /// are required, `limit` is optional. [`Headers::new`] falls back to it
+    /// if none of the separate headers are found.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a required key is missing or if a
+    /// value cannot be parsed.
+    pub fn from_structured(value: &str) -> std::result::Result<Self, Error> {
+        structured::parse(value)
+    }
+
+    /// Extracts rate limits from the structured `RateLimit` header,
+    /// or `None` if it is not present
+    fn from_structured_header(headers: &CaseSensitiveHeaderMap) -> Option<Result<Self>> {
+        let (name, value) = structured::get(headers)?;
+        Some(
+            convert::parse_header(name, value, structured::parse).map(|parsed| Headers {
+                matched_headers: vec![Cow::Borrowed(name)],
+                ..parsed
+            }),
+        )
+    }
+
+    /// Extracts rate limits from HTTP headers, only considering the headers
+    /// of the given vendor.
+    ///
+    /// Use this if the vendor is known from the context, because some vendors
+    /// send the same header names in different formats.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the given header map does not contain
+    /// all required headers of the vendor or if the header values cannot be
+    /// parsed.
+    pub fn new_with_vendor<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+        vendor: Vendor,
+    ) -> std::result::Result<Self, Error> {
+        Self::from_header_map_with_vendor(&headers.into(), vendor)
+    }
+
+    /// Extracts rate limits of the given vendor from a borrowed header map.
+    ///
+    /// See [`Headers::new_with_vendor`] for details.
+    pub(crate) fn from_header_map_with_vendor(
+        headers: &CaseSensitiveHeaderMap,
+        vendor: Vendor,
+    ) -> Result<Self> {
+        match Self::from_variants(headers, Self::variants_of(vendor)) {
+            Err(e) if e.is_missing() && vendor == Vendor::Standard => {
+                Self::from_structured_header(headers).unwrap_or(Err(e))
+            }
+            result => result,
+        }
+    }
+
+    /// Get the variants of the given vendor
+    ///
+    /// The variants of a vendor are contiguous in one of the tables, so this
+    /// is a sub-slice. It is empty if there are no rate limit headers for the
+    /// vendor (e.g. because it only sends `Retry-After`).
+    fn variants_of(vendor: Vendor) -> &'static [RateLimitVariant] {
+        let table = if VENDOR_HINT_HEADERS
+            .iter()
+            .any(|variant| variant.vendor == vendor)
+        {
+            VENDOR_HINT_HEADERS
+        } else {
+            RATE_LIMIT_HEADERS
+        };
+        let start = table
+            .iter()
+            .position(|variant| variant.vendor == vendor)
+            .unwrap_or(table.len());
+        let len = table[start..]
+            .iter()
+            .take_while(|variant| variant.vendor == vendor)
+            .count();
+        &table[start..start + len]
+    }
+
+    /// Extracts token limits from HTTP headers
+    ///
+    /// Some APIs, like OpenAI, limit the number of tokens in addition to
+    /// the number of requests. The request limits are returned by
+    /// [`Headers::new`], the token limits by this function.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the given header map does not contain
+    /// all required token limit headers or if the header values cannot be parsed.
+    pub fn tokens<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
+        Self::from_fields(&headers.into(), TOKEN_LIMIT_HEADERS)
+    }
+
+    /// Extracts rate limits from a borrowed header map,
+    /// only considering the given variants.
+    ///
+    /// If all headers of a variant are present, only that variant is used,
+    /// so that all fields are read from the headers of the same vendor.
+    /// Otherwise, the fields are looked up in all variants separately.
+    fn from_variants(
+        headers: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Result<Self> {
+        match Self::most_specific_match(headers, variants) {
+            Some(variant) => Self::from_fields(headers, std::slice::from_ref(variant)),
+            None => Self::from_fields(headers, variants),
+        }
+        .map(|rate| rate.with_other_limits(headers))
+    }
+
+    /// Extracts rate limits from the first variant whose headers are all
+    /// present, or `None` if there is no such variant
+    ///
+    /// Unlike [`Headers::from_header_map`], there is no lenient fallback.
+    pub(crate) fn from_complete_variant(headers: &CaseSensitiveHeaderMap) -> Option<Result<Self>> {
+        let variant = Self::most_specific_match(headers, RATE_LIMIT_HEADERS)?;
+        Some(
+            Self::from_fields(headers, std::slice::from_ref(variant))
+                .map(|rate| rate.with_other_limits(headers)),
+        )
+    }
+
+    /// Add the rate limits of other dimensions than requests, which the
+    /// vendor sends in separate headers (see [`Headers::other_limits`])
+    ///
+    /// They are optional, so they are skipped if missing or invalid.
+    fn with_other_limits(mut self, headers: &CaseSensitiveHeaderMap) -> Self {
+        let tokens = Self::most_specific_match(headers, TOKEN_LIMIT_HEADERS)
+            .filter(|variant| variant.vendor == self.vendor)
+            .and_then(|variant| Self::from_fields(headers, std::slice::from_ref(variant)).ok());
+        if let Some(tokens) = tokens {
+            self.matched_headers.extend(tokens.matched_headers);
+            self.other_limits.push(NamedLimit {
+                name: "tokens".to_string(),
+                limit: tokens.limit,
+                remaining: tokens.remaining,
+                reset: tokens.reset,
+            });
+        }
+        self
+    }
+
+    /// Extracts rate limits from a borrowed header map by looking up each
+    /// field in the given variants separately.
+    ///
+    /// This is lenient towards incomplete headers (e.g. a missing limit
+    /// header), but the fields may be read from the headers of different
+    /// vendors.
+    fn from_fields(
+        headers: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Result<Self> {
+        let (name, value, remaining_variant) = Self::get_remaining(headers, variants)?;
+        let remaining = if remaining_variant.remaining_percent_used {
+            let percent = convert::parse_header(name, value, convert::to_percent)?;
+            let (limit_name, limit, _) =
+                Self::get_rate_limit(headers, std::slice::from_ref(remaining_variant))?;
+            let limit = convert::parse_header(limit_name, limit, |value| Limit::new(value))?;
+            Remaining::from_percent_used(limit.count, percent)
         } else {
-            return Err(Error::MissingUsed);
+            convert::parse_header(name, value, Remaining::new)?
         };
 
-        let (value, kind) = Self::get_reset(&headers)?;
-        let reset = ResetTime::new(value, kind)?;
+        let mut matched_headers = vec![name];
+
+        let (limit, used, variant) =
+            if let Ok((name, limit, variant)) = Self::get_rate_limit(headers, variants) {
+                let limit = convert::parse_header(name, limit, |value| Limit::new(value))?;
+                matched_headers.push(name);
+                // Some vendors send the number of used requests in addition
+                // to the limit (e.g. Gitlab's `RateLimit-Observed`).
+                let used = variant
+                    .used_header
+                    .and_then(|name| variant.header(headers, name))
+                    .map(|(name, used)| {
+                        matched_headers.push(name);
+                        convert::parse_header(name, used, Used::new)
+                    })
+                    .transpose()?;
+                (Some(limit), used.map(|used| used.count), variant)
+            } else {
+                match Self::get_used(headers, variants) {
+                    Ok((name, used_value, variant)) => {
+                        // The site provides a `used` header, but no `limit` header.
+                        // Therefore we have to calculate the limit from used and remaining.
+                        // Pathological values saturate instead of overflowing.
+                        let used = convert::parse_header(name, used_value, Used::new)?.count;
+                        let limit = used.saturating_add(remaining.count);
+                        matched_headers.push(name);
+                        (Some(Limit::from(limit)), Some(used), variant)
+                    }
+                    // Token bucket APIs and some gateways only provide the
+                    // remaining requests, so the limit is unknown.
+                    // Variants which never provide a limit are preferred.
+                    Err(_) => (
+                        None,
+                        None,
+                        Self::get_token_bucket(headers, variants).unwrap_or(remaining_variant),
+                    ),
+                }
+            };
+
+        let variant = Self::disambiguate(headers, variants, variant);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(vendor = %variant.vendor, remaining_header = name, "matched rate limit variant");
+
+        let (reset_headers, resets): (Vec<_>, Vec<_>) =
+            Self::parse_resets(headers, variants, Some(variant))?
+                .into_iter()
+                .unzip();
+        let reset = Self::soonest_reset(&resets);
+        // Vendors may share reset headers, which are only parsed once.
+        for name in reset_headers {
+            if !matched_headers.contains(&name) {
+                matched_headers.push(name);
+            }
+        }
+
+        let reset_after = Self::get_seconds(headers, variant, variant.reset_after_header)?;
+        let window = Self::get_seconds(headers, variant, variant.window_header)?;
+        matched_headers.extend(reset_after.iter().chain(&window).map(|&(name, _)| name));
+
+        let throttle_scope = Self::get_string(headers, variant, variant.throttle_scope_header)?;
+        let resource = Self::get_string(headers, variant, variant.resource_header)?;
+        matched_headers.extend(
+            throttle_scope
+                .iter()
+                .chain(&resource)
+                .map(|&(name, _)| name),
+        );
+
+        let near_limit = variant
+            .near_limit_header
+            .and_then(|name| variant.header(headers, name))
+            .map(|(name, value)| {
+                matched_headers.push(name);
+                convert::parse_header(name, value, convert::to_bool)
+            })
+            .transpose()?;
+
+        // A window header takes precedence over a window in the limit header
+        let window = window
+            .map(|(_, window)| window)
+            .or(limit.and_then(|limit| limit.window))
+            .or(variant.duration);
+        let limit = limit.map(|limit| limit.count);
+
+        // The remaining requests may disagree with the used requests if the
+        // client already went over the limit. Don't report any left then.
+        let remaining = match (limit, used) {
+            (Some(limit), Some(used)) if used > limit => 0,
+            _ => remaining.count,
+        };
 
         Ok(Headers {
-            limit: limit.count,
-            remaining: remaining.count,
+            limit,
+            remaining,
+            used,
             reset,
-            window: variant.duration,
+            window,
             vendor: variant.vendor,
+            reset_after: reset_after.map(|(_, reset_after)| reset_after),
+            throttle_scope: throttle_scope.map(|(_, throttle_scope)| throttle_scope),
+            resource: resource.map(|(_, resource)| resource),
+            near_limit,
+            matched_headers: matched_headers.into_iter().map(Cow::Borrowed).collect(),
+            other_limits: Vec::new(),
         })
     }
 
-    /// Get the number of requests allowed in the time window
-    /// from the given header map
-    fn get_rate_limit(
+    /// Parse a header with a number of seconds into a duration,
+    /// or `None` if the variant or the header map doesn't have it
+    fn get_seconds(
         header_map: &CaseSensitiveHeaderMap,
-    ) -> Result<(&HeaderValue, RateLimitVariant)> {
-        let variants = &RATE_LIMIT_HEADERS;
+        variant: &RateLimitVariant,
+        name: Option<&'static str>,
+    ) -> Result<Option<(&'static str, Duration)>> {
+        name.and_then(|name| variant.header(header_map, name))
+            .map(|(name, value)| {
+                let seconds = convert::parse_header(name, value, convert::to_seconds_ceil)?;
+                let seconds = i64::try_from(seconds).unwrap_or(i64::MAX);
+                Ok((name, Duration::seconds(seconds)))
+            })
+            .transpose()
+    }
 
-        for variant in variants.iter() {
-            if let Some(limit) = &variant.limit_header {
-                if let Some(value) = header_map.get(limit) {
-                    return Ok((value, variant.clone()));
+    /// Read a header with a free-form value (e.g. a scope or resource),
+    /// or `None` if the variant or the header map doesn't have it
+    fn get_string(
+        header_map: &CaseSensitiveHeaderMap,
+        variant: &RateLimitVariant,
+        name: Option<&'static str>,
+    ) -> Result<Option<(&'static str, String)>> {
+        name.and_then(|name| variant.header(header_map, name))
+            .map(|(name, value)| {
+                let value =
+                    convert::parse_header(name, value, |value| Ok(value.trim().to_string()))?;
+                Ok((name, value))
+            })
+            .transpose()
+    }
+
+    /// Check if the given header map contains any known rate limit header
+    pub(crate) fn contains_any(header_map: &CaseSensitiveHeaderMap) -> bool {
+        Self::known_header_names().any(|name| header_map.get(name).is_some())
+    }
+
+    /// Iterate over the names of all known rate limit headers
+    ///
+    /// Names which are used by more than one vendor are returned once per
+    /// vendor.
+    pub(crate) fn known_header_names() -> impl Iterator<Item = &'static str> {
+        known_variants()
+            .flat_map(|variant| {
+                variant
+                    .limit_header
+                    .into_iter()
+                    .chain(variant.used_header)
+                    .chain([variant.remaining_header])
+                    .chain(variant.reset_headers.iter().map(|&(name, _)| name))
+                    .chain(variant.reset_after_header)
+                    .chain(variant.window_header)
+                    .chain(variant.throttle_scope_header)
+                    .chain(variant.resource_header)
+                    .chain(variant.near_limit_header)
+                    .chain(variant.alternate_names.iter().copied())
+                    .chain(variant.marker_headers.iter().copied())
+            })
+            .chain(structured::STRUCTURED_HEADERS)
+    }
+
+    /// Make sure that the given header map matches at most one vendor
+    ///
+    /// A variant matches if all of its headers are present. Matches whose
+    /// headers are a subset of another match are not considered ambiguous,
+    /// because the more specific variant is the better fit (e.g. Gitlab
+    /// sends a superset of the Heroku headers). Neither are matches with the
+    /// same headers as an earlier match: such variants can only be told
+    /// apart with a vendor hint, so the first one is always picked.
+    pub(crate) fn check_ambiguity(header_map: &CaseSensitiveHeaderMap) -> Result<()> {
+        let matches = Self::complete_matches(header_map, RATE_LIMIT_HEADERS);
+        let vendors: Vec<Vendor> = matches
+            .iter()
+            .enumerate()
+            .filter(|&(i, (_, names))| {
+                !matches[..i]
+                    .iter()
+                    .any(|(_, earlier)| earlier.len() == names.len() && is_subset(names, earlier))
+                    && !matches
+                        .iter()
+                        .any(|(_, other)| other.len() > names.len() && is_subset(names, other))
+            })
+            .map(|(_, (variant, _))| variant.vendor)
+            .collect();
+
+        if vendors.len() > 1 {
+            return Err(Error::AmbiguousVendor { vendors });
+        }
+        Ok(())
+    }
+
+    /// Get the variants whose headers are all present in the given header
+    /// map, along with the names under which their headers were found
+    fn complete_matches(
+        header_map: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Vec<(&'static RateLimitVariant, Vec<&'static str>)> {
+        variants
+            .iter()
+            .filter_map(|variant| Some((variant, variant.present_headers(header_map)?)))
+            .collect()
+    }
+
+    /// Get the first variant whose headers are all present in the given
+    /// header map, unless another such variant has a superset of its headers
+    ///
+    /// The more specific variant is the better fit, e.g. Gitlab sends the
+    /// headers of the draft plus `RateLimit-Observed`.
+    fn most_specific_match(
+        header_map: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Option<&'static RateLimitVariant> {
+        let matches = Self::complete_matches(header_map, variants);
+        matches
+            .iter()
+            .find(|(_, names)| {
+                !matches
+                    .iter()
+                    .any(|(_, other)| other.len() > names.len() && is_subset(names, other))
+            })
+            .map(|&(variant, _)| variant)
+    }
+
+    /// Pick the variant whose reset header can be parsed, among all
+    /// variants which share the limit, used and remaining headers of the
+    /// given variant.
+    ///
+    /// Some vendors use the same header names, but a different format for
+    /// the reset time (e.g. Vimeo and Mastodon).
+    fn disambiguate(
+        header_map: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+        variant: &'static RateLimitVariant,
+    ) -> &'static RateLimitVariant {
+        variants
+            .iter()
+            .filter(|other| {
+                other.limit_header == variant.limit_header
+                    && other.used_header == variant.used_header
+                    && other.remaining_header == variant.remaining_header
+            })
+            .find(|other| other.matches(header_map))
+            .map_or(variant, |other| {
+                #[cfg(feature = "tracing")]
+                if other.vendor != variant.vendor {
+                    tracing::debug!(
+                        from = %variant.vendor,
+                        to = %other.vendor,
+                        "reset time format matches a different vendor"
+                    );
                 }
+                other
+            })
+    }
+
+    /// Get the number of requests allowed in the time window
+    /// from the given header map
+    fn get_rate_limit<'a>(
+        header_map: &'a CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Result<(&'static str, &'a HeaderValue, &'static RateLimitVariant)> {
+        for variant in variants.iter() {
+            if let Some((name, value)) = variant
+                .limit_header
+                .and_then(|name| variant.header(header_map, name))
+            {
+                return Ok((name, value, variant));
             }
         }
-        Err(Error::MissingLimit)
+        Err(Error::MissingLimit {
+            candidates: Self::candidates(variants, |variant| variant.limit_header),
+        })
     }
 
     /// Get the number of requests used in the time window
     /// from the given header map
-    fn get_used(header_map: &CaseSensitiveHeaderMap) -> Result<(&HeaderValue, RateLimitVariant)> {
-        let variants = &RATE_LIMIT_HEADERS;
-
+    fn get_used<'a>(
+        header_map: &'a CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Result<(&'static str, &'a HeaderValue, &'static RateLimitVariant)> {
         for variant in variants.iter() {
-            if let Some(used) = &variant.used_header {
-                if let Some(value) = header_map.get(used) {
-                    return Ok((value, variant.clone()));
-                }
+            if let Some((name, value)) = variant
+                .used_header
+                .and_then(|name| variant.header(header_map, name))
+            {
+                return Ok((name, value, variant));
             }
         }
-        Err(Error::MissingUsed)
+        Err(Error::MissingUsed {
+            candidates: Self::candidates(variants, |variant| variant.used_header),
+        })
+    }
+
+    /// Get the variant of a token bucket API, which only provides
+    /// the number of remaining requests but no limit
+    fn get_token_bucket(
+        header_map: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Option<&'static RateLimitVariant> {
+        variants.iter().find(|variant| {
+            variant.limit_header.is_none()
+                && variant.used_header.is_none()
+                && variant
+                    .header(header_map, variant.remaining_header)
+                    .is_some()
+        })
     }
 
     /// Get the number of requests remaining in the time window
     /// from the given header map
-    fn get_remaining(header_map: &CaseSensitiveHeaderMap) -> Result<&HeaderValue> {
-        let variants = &RATE_LIMIT_HEADERS;
-
+    fn get_remaining<'a>(
+        header_map: &'a CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Result<(&'static str, &'a HeaderValue, &'static RateLimitVariant)> {
         for variant in variants.iter() {
-            if let Some(value) = header_map.get(&variant.remaining_header) {
-                return Ok(value);
+            if let Some((name, value)) = variant.header(header_map, variant.remaining_header) {
+                return Ok((name, value, variant));
             }
         }
-        Err(Error::MissingRemaining)
+        Err(Error::MissingRemaining {
+            candidates: Self::candidates(variants, |variant| Some(variant.remaining_header)),
+        })
     }
 
-    /// Get the time at which the rate limit will be reset
-    /// from the given header map
-    fn get_reset(header_map: &CaseSensitiveHeaderMap) -> Result<(&HeaderValue, ResetTimeKind)> {
-        let variants = &RATE_LIMIT_HEADERS;
+    /// Get all reset times which can be parsed from the given header map
+    ///
+    /// Some vendors send more than one reset header. The candidates are
+    /// returned in the order in which the variants are checked.
+    /// [`Headers::new`] picks the soonest reset time which is still in the
+    /// future.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the header map contains no reset
+    /// header or if none of the reset headers can be parsed.
+    pub fn reset_candidates<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> Result<Vec<ResetTime>> {
+        Ok(
+            Self::parse_resets(&headers.into(), RATE_LIMIT_HEADERS, None)?
+                .into_iter()
+                .map(|(_, reset)| reset)
+                .collect(),
+        )
+    }
 
-        for variant in variants.iter() {
-            if let Some(value) = header_map.get(&variant.reset_header) {
-                return Ok((value, variant.reset_kind));
+    /// Parse all reset times from the given header map,
+    /// together with the names of their headers
+    ///
+    /// Vendors may share a reset header, but use a different format for it
+    /// (e.g. `Standard` and Twitch). The reset headers of the matched variant
+    /// are only parsed in the format of that variant.
+    fn parse_resets(
+        header_map: &CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+        matched: Option<&RateLimitVariant>,
+    ) -> Result<Vec<(&'static str, ResetTime)>> {
+        let mut resets = Vec::new();
+        let mut error = None;
+        for (name, value, kind) in Self::get_resets(header_map, variants)? {
+            if matched.is_some_and(|matched| {
+                matched
+                    .reset_headers
+                    .iter()
+                    .any(|&(other, other_kind)| other == name && other_kind != kind)
+            }) {
+                continue;
+            }
+            match convert::parse_header(name, value, |value| ResetTime::parse(value, kind)) {
+                Ok(reset) => resets.push((name, reset)),
+                Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(header = name, ?kind, error = %e, "skipping reset header");
+                    error.get_or_insert(e);
+                }
             }
         }
-        Err(Error::MissingReset)
+        match error {
+            Some(e) if resets.is_empty() => Err(e),
+            _ => Ok(resets),
+        }
     }
 
-    /// Get the number of requests allowed in the time window
+    /// Pick the soonest reset time which is still in the future.
+    /// If all reset times have passed, the first one is used.
+    fn soonest_reset(resets: &[ResetTime]) -> ResetTime {
+        let now = OffsetDateTime::now_utc();
+        resets
+            .iter()
+            .filter(|reset| reset.seconds_with_reference(now) > 0)
+            .min_by_key(|reset| reset.to_datetime(now))
+            .or_else(|| resets.first())
+            .copied()
+            .unwrap_or(ResetTime::Seconds(0))
+    }
+
+    /// Get all reset headers from the given header map
+    fn get_resets<'a>(
+        header_map: &'a CaseSensitiveHeaderMap,
+        variants: &'static [RateLimitVariant],
+    ) -> Result<Vec<(&'static str, &'a HeaderValue, ResetTimeKind)>> {
+        // Vendors may share reset headers (e.g. Github and Algolia),
+        // which only need to be parsed once.
+        let mut resets: Vec<(&'static str, &'a HeaderValue, ResetTimeKind)> = Vec::new();
+        for variant in variants {
+            for &(name, kind) in variant.reset_headers {
+                let Some((name, value)) = variant.header(header_map, name) else {
+                    continue;
+                };
+                if !resets
+                    .iter()
+                    .any(|&(other, _, other_kind)| other == name && other_kind == kind)
+                {
+                    resets.push((name, value, kind));
+                }
+            }
+        }
+
+        if resets.is_empty() {
+            return Err(Error::MissingReset {
+                candidates: Self::candidates(variants, |variant| {
+                    variant.reset_headers.iter().map(|&(name, _)| name)
+                }),
+            });
+        }
+        Ok(resets)
+    }
+
+    /// Get all header names of the known variants for a given field,
+    /// in the order in which they are checked
+    fn candidates<I>(
+        variants: &'static [RateLimitVariant],
+        headers: impl Fn(&'static RateLimitVariant) -> I,
+    ) -> Vec<String>
+    where
+        I: IntoIterator<Item = &'static str>,
+    {
+        let mut candidates: Vec<String> = Vec::new();
+        for name in variants.iter().flat_map(headers) {
+            if !candidates.iter().any(|candidate| candidate == name) {
+                candidates.push(name.to_string());
+            }
+        }
+        candidates
+    }
+
+    /// Get the number of requests allowed in the time window, if known
     #[must_use]
-    pub const fn limit(&self) -> usize {
+    pub const fn limit(&self) -> Option<usize> {
         self.limit
     }
 
@@ -152,13 +761,155 @@ impl Headers {
     pub const fn reset(&self) -> ResetTime {
         self.reset
     }
+
+    /// Get the time at which the rate limit will be reset,
+    /// preferring the relative reset time if there is one
+    ///
+    /// A relative reset time (see [`Headers::reset_after`]) does not depend
+    /// on the clocks of client and server being in sync, so it is more
+    /// reliable than an absolute one.
+    #[must_use]
+    pub fn effective_reset(&self) -> ResetTime {
+        self.reset_after
+            .map_or(self.reset, ResetTime::from_duration)
+    }
+
+    /// Compare the rate limits by their values, ignoring the vendor
+    ///
+    /// The names of the matched headers are ignored as well, because they
+    /// depend on the vendor. All other fields are compared.
+    /// This is useful if the same rate limits were parsed under different
+    /// vendor guesses.
+    #[must_use]
+    pub fn eq_ignoring_vendor(&self, other: &Self) -> bool {
+        self.limit == other.limit
+            && self.remaining == other.remaining
+            && self.reset == other.reset
+            && self.window == other.window
+            && self.reset_after == other.reset_after
+            && self.used == other.used
+            && self.near_limit == other.near_limit
+            && self.throttle_scope == other.throttle_scope
+            && self.resource == other.resource
+            && self.other_limits == other.other_limits
+    }
+
+    /// Get the number of requests per second which can be made to use up
+    /// the remaining quota evenly until the rate limit resets
+    ///
+    /// This is useful for pacing requests on the client side. If the reset
+    /// is less than a second away (or has passed), the remaining requests are
+    /// spread over one second to avoid dividing by zero.
+    #[must_use]
+    pub fn safe_rate(&self) -> f64 {
+        let seconds = self.effective_reset().seconds().max(1);
+        self.remaining as f64 / seconds as f64
+    }
+
+    /// Check if the client already went over the limit, i.e. more requests
+    /// were used than the limit allows
+    ///
+    /// In that case, [`Headers::remaining`] is zero. Returns `false` if the
+    /// number of used requests or the limit is unknown.
+    #[must_use]
+    pub fn over_limit(&self) -> bool {
+        self.used
+            .zip(self.limit)
+            .is_some_and(|(used, limit)| used > limit)
+    }
+
+    /// Get the fraction of the limit which was used, between 0.0 and 1.0
+    ///
+    /// Returns `None` if the limit is unknown. A limit of zero counts as
+    /// fully used.
+    #[must_use]
+    pub fn usage_ratio(&self) -> Option<f64> {
+        let limit = self.limit?;
+        if limit == 0 {
+            return Some(1.0);
+        }
+        Some(limit.saturating_sub(self.remaining) as f64 / limit as f64)
+    }
+
+    /// Check if the client is close to the limit, i.e. the
+    /// [usage ratio](Headers::usage_ratio) is at least `threshold`
+    /// (e.g. `0.8` for 80%)
+    ///
+    /// If the vendor signals it explicitly (see [`Headers::near_limit`]),
+    /// that signal takes precedence over the threshold. Returns `false` if
+    /// neither the signal nor the limit is known.
+    #[must_use]
+    pub fn is_near_limit(&self, threshold: f64) -> bool {
+        self.near_limit
+            .unwrap_or_else(|| self.usage_ratio().is_some_and(|ratio| ratio >= threshold))
+    }
+
+    /// Project the number of remaining requests at the given point in time,
+    /// assuming no further requests are made
+    ///
+    /// The quota is assumed to refill linearly at a rate of `limit` requests
+    /// per [window](Headers::window) (like Reddit's token bucket), and to be
+    /// fully restored once the rate limit resets. Relative reset times are
+    /// taken relative to now. Returns `None` if the window or the limit is
+    /// unknown.
+    #[must_use]
+    pub fn projected_remaining(&self, at: OffsetDateTime) -> Option<usize> {
+        self.projected_remaining_with_reference(at, OffsetDateTime::now_utc())
+    }
+
+    /// Project the number of remaining requests at `at`, using `now` as the
+    /// point in time at which the headers were received
+    fn projected_remaining_with_reference(
+        &self,
+        at: OffsetDateTime,
+        now: OffsetDateTime,
+    ) -> Option<usize> {
+        let window = self.window?;
+        let limit = self.limit?;
+        if at <= now {
+            return Some(self.remaining);
+        }
+        if at >= self.effective_reset().to_datetime(now) || !window.is_positive() {
+            return Some(limit);
+        }
+        let refill = limit as f64 * ((at - now) / window);
+        Some(self.remaining.saturating_add(refill as usize).min(limit))
+    }
+
+    /// Get the limit, remaining requests and reset time as a tuple
+    ///
+    /// This is handy for logging all three at once.
+    #[must_use]
+    pub const fn as_tuple(&self) -> (Option<usize>, usize, ResetTime) {
+        (self.limit, self.remaining, self.reset)
+    }
+
+    /// Get the time window of the rate limit, or an estimate if it is unknown
+    ///
+    /// If the vendor does not specify a window, but the reset time is
+    /// given in seconds and some requests were already made in the current
+    /// window (`remaining < limit`), the window is at least as long as the
+    /// time until reset. This lower bound is returned as a heuristic estimate.
+    /// The actual window can be longer.
+    #[must_use]
+    pub fn estimated_window(&self) -> Option<Duration> {
+        match (self.window, self.reset) {
+            (Some(window), _) => Some(window),
+            (None, ResetTime::Seconds(_))
+                if self.limit.is_some_and(|limit| self.remaining < limit) =>
+            {
+                Some(self.reset.duration())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for Headers {
     type Err = Error;
 
     fn from_str(map: &str) -> Result<Self> {
-        Headers::new(CaseSensitiveHeaderMap::from_str(map)?)
+        Headers::from_header_map(&CaseSensitiveHeaderMap::from_str(map)?)
     }
 }
 
@@ -168,6 +919,7 @@ mod tests {
     use crate::casesensitive_headermap::HeaderMapExt;
     use headers::HeaderMap;
     use indoc::indoc;
+    use std::cmp::Ordering;
     use time::{macros::datetime, OffsetDateTime};
 
     #[test]
@@ -176,6 +928,61 @@ mod tests {
         assert_eq!(limit.count, 23);
     }
 
+    #[test]
+    fn parse_limit_value_with_window() {
+        let limit = Limit::new("100").unwrap();
+        assert_eq!(limit.count, 100);
+        assert_eq!(limit.window, None);
+
+        let limit = Limit::new("100, 100;window=60").unwrap();
+        assert_eq!(limit.count, 100);
+        assert_eq!(limit.window, Some(Duration::MINUTE));
+
+        // Like other lists, the last element is used, with or without parameters
+        let limit = Limit::new("10, 10;w=1, 50;w=60").unwrap();
+        assert_eq!(limit.count, 50);
+        assert_eq!(limit.window, Some(Duration::MINUTE));
+
+        let limit = Limit::new("100, 50").unwrap();
+        assert_eq!(limit.count, 50);
+        assert_eq!(limit.window, None);
+
+        let limit = Limit::new("100, 50;w=60").unwrap();
+        assert_eq!(limit.count, 50);
+        assert_eq!(limit.window, Some(Duration::MINUTE));
+
+        let limit = Limit::new("50;w=60").unwrap();
+        assert_eq!(limit.count, 50);
+        assert_eq!(limit.window, Some(Duration::MINUTE));
+
+        let limit = Limit::new("50;policy=\"default\"").unwrap();
+        assert_eq!(limit.count, 50);
+        assert_eq!(limit.window, None);
+
+        assert!(Limit::new("100, 100;window=soon").is_err());
+    }
+
+    #[test]
+    fn parse_windowed_limit_header() {
+        let headers = indoc! {"
+            RateLimit-Limit: 100, 100;window=60
+            RateLimit-Remaining: 50
+            RateLimit-Reset: 20
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), Some(100));
+        assert_eq!(rate.window, Some(Duration::MINUTE));
+
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            RateLimit-Remaining: 50
+            RateLimit-Reset: 20
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), Some(100));
+        assert_eq!(rate.window, None);
+    }
+
     #[test]
     fn parse_invalid_limit_value() {
         assert!(Limit::new("foo").is_err());
@@ -186,97 +993,509 @@ mod tests {
     #[test]
     fn parse_vendor() {
         let map = CaseSensitiveHeaderMap::from_str("x-ratelimit-limit: 5000").unwrap();
-        let (_, variant) = Headers::get_rate_limit(&map).unwrap();
+        let (_, _, variant) = Headers::get_rate_limit(&map, RATE_LIMIT_HEADERS).unwrap();
         assert_eq!(variant.vendor, Vendor::Github);
 
         let map = CaseSensitiveHeaderMap::from_str("RateLimit-Limit: 5000").unwrap();
-        let (_, variant) = Headers::get_rate_limit(&map).unwrap();
+        let (_, _, variant) = Headers::get_rate_limit(&map, RATE_LIMIT_HEADERS).unwrap();
         assert_eq!(variant.vendor, Vendor::Standard);
     }
 
     #[test]
-    fn parse_remaining_value() {
-        let remaining = Remaining::new("  23 ").unwrap();
-        assert_eq!(remaining.count, 23);
+    fn parse_vendor_name() {
+        assert_eq!("github".parse::<Vendor>().unwrap(), Vendor::Github);
+        assert_eq!("GitHub".parse::<Vendor>().unwrap(), Vendor::Github);
+        assert_eq!(" GITLAB ".parse::<Vendor>().unwrap(), Vendor::Gitlab);
+        assert!("foo".parse::<Vendor>().is_err());
     }
 
     #[test]
-    fn parse_invalid_remaining_value() {
-        assert!(Remaining::new("foo").is_err());
-        assert!(Remaining::new("0 foo").is_err());
-        assert!(Remaining::new("bar 0").is_err());
+    fn vendor_display_roundtrip() {
+        for vendor in [
+            Vendor::Standard,
+            Vendor::Reddit,
+            Vendor::Github,
+            Vendor::Algolia,
+            Vendor::Twitter,
+            Vendor::Twitch,
+            Vendor::Vimeo,
+            Vendor::Jira,
+            Vendor::Mastodon,
+            Vendor::Square,
+            Vendor::Bitbucket,
+            Vendor::Discord,
+            Vendor::SendGrid,
+            Vendor::Gitlab,
+            Vendor::MicrosoftGraph,
+            Vendor::Akamai,
+            Vendor::Fastly,
+            Vendor::Heroku,
+            Vendor::OpenAI,
+            Vendor::Pinterest,
+            Vendor::Notion,
+        ] {
+            assert_eq!(vendor.to_string().parse::<Vendor>().unwrap(), vendor);
+        }
+        assert_eq!(Vendor::Github.to_string(), "github");
     }
 
     #[test]
-    fn parse_reset_timestamp() {
-        let v = HeaderValue::from_str("1350085394").unwrap();
+    fn vendor_docs_url() {
+        assert_eq!(Vendor::Standard.docs_url(), None);
         assert_eq!(
-            ResetTime::new(&v, ResetTimeKind::Timestamp).unwrap(),
-            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+            Vendor::MicrosoftGraph.docs_url(),
+            Some("https://learn.microsoft.com/en-us/graph/throttling")
         );
+        assert!(Vendor::Github
+            .docs_url()
+            .is_some_and(|url| url.starts_with("https://docs.github.com/")));
     }
 
     #[test]
-    fn parse_reset_seconds() {
-        let v = HeaderValue::from_str("100").unwrap();
+    fn list_known_variants() {
+        let github = known_variants()
+            .find(|variant| variant.vendor() == Vendor::Github)
+            .unwrap();
+        assert_eq!(github.limit_header(), Some("x-ratelimit-limit"));
+        assert_eq!(github.remaining_header(), "x-ratelimit-remaining");
         assert_eq!(
-            ResetTime::new(&v, ResetTimeKind::Seconds).unwrap(),
-            ResetTime::Seconds(100)
+            github.reset_headers(),
+            &[("x-ratelimit-reset", ResetTimeKind::Timestamp)]
         );
-    }
-
-    #[test]
-    fn parse_reset_datetime() {
-        let v = HeaderValue::from_str("Tue, 15 Nov 1994 08:12:31 GMT").unwrap();
-        let d = ResetTime::new(&v, ResetTimeKind::ImfFixdate);
         assert_eq!(
-            d.unwrap(),
-            ResetTime::DateTime(datetime!(1994-11-15 8:12:31 UTC))
+            known_variants().count(),
+            RATE_LIMIT_HEADERS.len() + VENDOR_HINT_HEADERS.len()
         );
-    }
-
-    #[test]
-    fn parse_header_map_newlines() {
-        let map = HeaderMap::from_raw(
-            "x-ratelimit-limit: 5000
-x-ratelimit-remaining: 4987
-x-ratelimit-reset: 1350085394
-",
-        )
-        .unwrap();
 
-        assert_eq!(map.len(), 3);
-        assert_eq!(
-            map.get("x-ratelimit-limit"),
-            Some(&HeaderValue::from_str("5000").unwrap())
-        );
-        assert_eq!(
-            map.get("x-ratelimit-remaining"),
-            Some(&HeaderValue::from_str("4987").unwrap())
-        );
-        assert_eq!(
-            map.get("x-ratelimit-reset"),
-            Some(&HeaderValue::from_str("1350085394").unwrap())
-        );
+        // Algolia uses the same headers as Github, so it's only used with a hint
+        let algolia = known_variants()
+            .find(|variant| variant.vendor() == Vendor::Algolia)
+            .unwrap();
+        assert_eq!(algolia.limit_header(), github.limit_header());
+        assert_eq!(algolia.reset_headers(), github.reset_headers());
     }
 
     #[test]
-    fn parse_github_headers() {
+    fn invalid_value_error_contains_header() {
         let headers = indoc! {"
             x-ratelimit-limit: 5000
-            x-ratelimit-remaining: 4987
+            x-ratelimit-remaining: lots
             x-ratelimit-reset: 1350085394
         "};
 
-        let rate = Headers::from_str(headers).unwrap();
-        assert_eq!(rate.limit(), 5000);
-        assert_eq!(rate.remaining(), 4987);
-        assert_eq!(
+        match Headers::from_str(headers) {
+            Err(Error::InvalidValueFor { header, value }) => {
+                assert_eq!(header, "x-ratelimit-remaining");
+                assert_eq!(value, "lots");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn used_overflow_saturates() {
+        let headers = format!(
+            "X-Ratelimit-Used: {}\nX-Ratelimit-Remaining: 1\nX-Ratelimit-Reset: 30",
+            usize::MAX
+        );
+        let rate = Headers::from_str(&headers).unwrap();
+        assert_eq!(rate.limit(), Some(usize::MAX));
+        assert_eq!(rate.used, Some(usize::MAX));
+        assert_eq!(rate.remaining(), 1);
+
+        let headers = format!(
+            "X-Ratelimit-Used: {max}\nX-Ratelimit-Remaining: {max}\nX-Ratelimit-Reset: 30",
+            max = usize::MAX
+        );
+        let rate = Headers::from_str(&headers).unwrap();
+        assert_eq!(rate.limit(), Some(usize::MAX));
+        assert_eq!(rate.remaining(), usize::MAX);
+    }
+
+    #[test]
+    fn remaining_above_limit_saturates() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 10
+            x-ratelimit-remaining: 20
+            x-ratelimit-reset: 1350085394
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.usage_ratio(), Some(0.0));
+
+        let headers = format!(
+            "x-ratelimit-limit: {}\nx-ratelimit-remaining: 0\nx-ratelimit-reset: 1350085394",
+            usize::MAX
+        );
+        let rate = Headers::from_str(&headers).unwrap();
+        assert_eq!(rate.usage_ratio(), Some(1.0));
+    }
+
+    #[test]
+    fn missing_header_error_contains_candidates() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 5000
+            X-RateLimit-Reset: 1350085394
+        "};
+
+        match Headers::from_str(headers) {
+            Err(Error::MissingRemaining { candidates }) => {
+                assert!(candidates.contains(&"x-ratelimit-remaining".to_string()));
+                assert!(candidates.contains(&"X-RateLimit-Remaining".to_string()));
+                // Duplicate header names are only listed once
+                assert_eq!(
+                    candidates
+                        .iter()
+                        .filter(|c| *c == "X-RateLimit-Remaining")
+                        .count(),
+                    1
+                );
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn detect_ambiguous_vendor() {
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: Tue, 15 Nov 1994 08:12:31 GMT
+            X-RateLimit-Next: 2023-01-01T00:00:00Z
+        "})
+        .unwrap();
+        assert!(matches!(
+            Headers::check_ambiguity(&headers),
+            Err(Error::AmbiguousVendor { vendors }) if vendors == [Vendor::Vimeo, Vendor::Akamai]
+        ));
+
+        // Gitlab headers are a superset of the Heroku headers
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            RateLimit-Limit: 60
+            RateLimit-Observed: 1
+            RateLimit-Remaining: 59
+            RateLimit-Reset: 1609844400
+        "})
+        .unwrap();
+        assert!(Headers::check_ambiguity(&headers).is_ok());
+    }
+
+    #[test]
+    fn parse_remaining_value() {
+        let remaining = Remaining::new("  23 ").unwrap();
+        assert_eq!(remaining.count, 23);
+    }
+
+    #[test]
+    fn parse_fractional_remaining_value() {
+        assert_eq!(Remaining::new("4.5").unwrap().count, 4);
+        assert_eq!(Remaining::new("0.0").unwrap().count, 0);
+        assert_eq!(Limit::new("10.9").unwrap().count, 10);
+    }
+
+    #[test]
+    fn parse_invalid_remaining_value() {
+        assert!(Remaining::new("foo").is_err());
+        assert!(Remaining::new("0 foo").is_err());
+        assert!(Remaining::new("bar 0").is_err());
+    }
+
+    #[test]
+    fn parse_reset_timestamp() {
+        let v = HeaderValue::from_str("1350085394").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Timestamp).unwrap(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_reset_seconds() {
+        let v = HeaderValue::from_str("100").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Seconds).unwrap(),
+            ResetTime::Seconds(100)
+        );
+    }
+
+    #[test]
+    fn parse_reset_seconds_date_fallback() {
+        let v = HeaderValue::from_str("Tue, 05 Jan 2021 11:00:00 GMT").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Seconds).unwrap(),
+            ResetTime::DateTime(datetime!(2021-01-05 11:00:00 UTC))
+        );
+        let v = HeaderValue::from_str("2021-01-05T11:00:00Z").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Auto).unwrap(),
+            ResetTime::DateTime(datetime!(2021-01-05 11:00:00 UTC))
+        );
+        let v = HeaderValue::from_str("soon").unwrap();
+        assert!(matches!(
+            ResetTime::new(&v, ResetTimeKind::Seconds),
+            Err(Error::InvalidValue(_))
+        ));
+
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 99
+            Ratelimit-Reset: Tue, 05 Jan 2021 11:00:00 GMT
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2021-01-05 11:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn parse_reset_fractional_seconds() {
+        let v = HeaderValue::from_str("1.2").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Seconds).unwrap(),
+            ResetTime::Seconds(2)
+        );
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Auto).unwrap(),
+            ResetTime::Seconds(2)
+        );
+    }
+
+    #[test]
+    fn parse_reset_auto() {
+        let v = HeaderValue::from_str("30").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Auto).unwrap(),
+            ResetTime::Seconds(30)
+        );
+
+        let v = HeaderValue::from_str("1350085394").unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Auto).unwrap(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+
+        let v = HeaderValue::from_str("-1").unwrap();
+        assert!(ResetTime::new(&v, ResetTimeKind::Auto).is_err());
+    }
+
+    #[test]
+    fn parse_reset_auto_threshold() {
+        let threshold = ResetTimeKind::AUTO_SECONDS_THRESHOLD;
+
+        let v = HeaderValue::from_str(&(threshold - 1).to_string()).unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Auto).unwrap(),
+            ResetTime::Seconds(threshold - 1)
+        );
+
+        let v = HeaderValue::from_str(&threshold.to_string()).unwrap();
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Auto).unwrap(),
+            ResetTime::DateTime(
+                OffsetDateTime::from_unix_timestamp(i64::try_from(threshold).unwrap()).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_reset_datetime() {
+        let v = HeaderValue::from_str("Tue, 15 Nov 1994 08:12:31 GMT").unwrap();
+        let d = ResetTime::new(&v, ResetTimeKind::ImfFixdate);
+        assert_eq!(
+            d.unwrap(),
+            ResetTime::DateTime(datetime!(1994-11-15 8:12:31 UTC))
+        );
+    }
+
+    #[test]
+    fn parse_reset_iso8601() {
+        for (value, expected) in [
+            ("20230101T000000Z", datetime!(2023-01-01 00:00:00 UTC)),
+            ("2023-01-01T00:00:00Z", datetime!(2023-01-01 00:00:00 UTC)),
+            (
+                "2023-01-01T00:00:00.123456Z",
+                datetime!(2023-01-01 00:00:00.123456 UTC),
+            ),
+            (
+                "2023-01-01T02:00:00+02:00",
+                datetime!(2023-01-01 00:00:00 UTC),
+            ),
+            ("2023-01-01T00:00:00", datetime!(2023-01-01 00:00:00 UTC)),
+        ] {
+            let v = HeaderValue::from_str(value).unwrap();
+            assert_eq!(
+                ResetTime::new(&v, ResetTimeKind::Iso8601).unwrap(),
+                ResetTime::DateTime(expected),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_reset_rfc3339() {
+        for value in [
+            "2023-01-01T00:00:00Z",
+            "2023-01-01T00:00:00+00:00",
+            "2023-01-01T02:00:00+02:00",
+            "2022-12-31T19:00:00.000-05:00",
+        ] {
+            let v = HeaderValue::from_str(value).unwrap();
+            let ResetTime::DateTime(d) = ResetTime::new(&v, ResetTimeKind::Rfc3339).unwrap() else {
+                panic!("{value} is not a date");
+            };
+            assert_eq!(d, datetime!(2023-01-01 00:00:00 UTC), "{value}");
+            assert_eq!(d.offset(), time::UtcOffset::UTC, "{value}");
+        }
+
+        // An offset is required
+        for value in ["2023-01-01T00:00:00", "20230101T000000Z", "2023-01-01"] {
+            let v = HeaderValue::from_str(value).unwrap();
+            assert!(
+                ResetTime::new(&v, ResetTimeKind::Rfc3339).is_err(),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_reset_sniff() {
+        for (value, expected) in [
+            ("30", ResetTime::Seconds(30)),
+            ("1.5", ResetTime::Seconds(2)),
+            (
+                "1350085394",
+                ResetTime::DateTime(datetime!(2012-10-12 23:43:14 UTC)),
+            ),
+            (
+                "Tue, 15 Nov 1994 08:12:31 GMT",
+                ResetTime::DateTime(datetime!(1994-11-15 08:12:31 UTC)),
+            ),
+            (
+                "2023-01-01T00:00:00.000000Z",
+                ResetTime::DateTime(datetime!(2023-01-01 00:00:00 UTC)),
+            ),
+            ("6m0s", ResetTime::Seconds(360)),
+        ] {
+            let v = HeaderValue::from_str(value).unwrap();
+            assert_eq!(
+                ResetTime::new(&v, ResetTimeKind::Sniff).unwrap(),
+                expected,
+                "{value}"
+            );
+        }
+
+        // Only Go-style durations are not accepted by `Auto`
+        let v = HeaderValue::from_str("1m30s").unwrap();
+        assert!(ResetTime::new(&v, ResetTimeKind::Auto).is_err());
+        assert_eq!(
+            ResetTime::new(&v, ResetTimeKind::Sniff).unwrap(),
+            ResetTime::Seconds(90)
+        );
+
+        let v = HeaderValue::from_str("soon").unwrap();
+        assert!(matches!(
+            ResetTime::new(&v, ResetTimeKind::Sniff),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn compare_reset_times() {
+        let future = ResetTime::DateTime(OffsetDateTime::now_utc() + Duration::hours(1));
+        let past = ResetTime::DateTime(datetime!(1994-11-15 8:12:31 UTC));
+
+        let now = OffsetDateTime::now_utc();
+        let cmp = |a: ResetTime, b: ResetTime| a.cmp_at(&b, now);
+
+        assert_eq!(cmp(ResetTime::Seconds(30), future), Ordering::Less);
+        assert_eq!(cmp(future, ResetTime::Seconds(30)), Ordering::Greater);
+        assert_eq!(cmp(ResetTime::Seconds(7200), future), Ordering::Greater);
+        assert_eq!(cmp(past, ResetTime::Seconds(0)), Ordering::Less);
+        assert_eq!(
+            cmp(ResetTime::Seconds(10), ResetTime::Seconds(20)),
+            Ordering::Less
+        );
+        assert_eq!(cmp(past, future), Ordering::Less);
+
+        // The reference time is fixed, so the order doesn't change
+        let in_a_minute = ResetTime::DateTime(now + Duration::minutes(1));
+        assert_eq!(cmp(ResetTime::Seconds(60), in_a_minute), Ordering::Equal);
+    }
+
+    #[test]
+    fn parse_header_map_newlines() {
+        let map = HeaderMap::from_raw(
+            "x-ratelimit-limit: 5000
+x-ratelimit-remaining: 4987
+x-ratelimit-reset: 1350085394
+",
+        )
+        .unwrap();
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(
+            map.get("x-ratelimit-limit"),
+            Some(&HeaderValue::from_str("5000").unwrap())
+        );
+        assert_eq!(
+            map.get("x-ratelimit-remaining"),
+            Some(&HeaderValue::from_str("4987").unwrap())
+        );
+        assert_eq!(
+            map.get("x-ratelimit-reset"),
+            Some(&HeaderValue::from_str("1350085394").unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_github_headers() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), 4987);
+        assert_eq!(
             rate.reset(),
             ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
         );
     }
 
+    #[test]
+    fn parse_coalesced_headers() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000, 5000
+            x-ratelimit-remaining: 10, 5
+            x-ratelimit-reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), 5);
+    }
+
+    #[test]
+    fn headers_as_tuple() {
+        let rate = Headers::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(
+            rate.as_tuple(),
+            (
+                Some(5000),
+                4987,
+                ResetTime::DateTime(datetime!(2012-10-12 23:43:14 UTC))
+            )
+        );
+    }
+
     #[test]
     fn parse_reddit_headers() {
         let headers = indoc! {"
@@ -286,11 +1505,937 @@ x-ratelimit-reset: 1350085394
         "};
 
         let rate = Headers::from_str(headers).unwrap();
-        assert_eq!(rate.limit(), 122);
+        assert_eq!(rate.limit(), Some(122));
         assert_eq!(rate.remaining(), 22);
+        assert_eq!(rate.used, Some(100));
         assert_eq!(rate.reset(), ResetTime::Seconds(30));
     }
 
+    #[test]
+    fn parse_twitter_headers() {
+        let headers = indoc! {"
+            x-rate-limit-limit: 900
+            x-rate-limit-remaining: 899
+            x-rate-limit-reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Twitter);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_twitter_v2_relative_reset() {
+        let headers = indoc! {"
+            x-rate-limit-limit: 900
+            x-rate-limit-remaining: 899
+            x-rate-limit-reset: 850
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Twitter);
+        assert_eq!(rate.reset(), ResetTime::Seconds(850));
+    }
+
+    #[test]
+    fn parse_twitter_legacy_headers() {
+        let headers = indoc! {"
+            X-Rate-Limit-Limit: 900
+            X-Rate-Limit-Remaining: 899
+            X-Rate-Limit-Reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Twitter);
+        assert_eq!(rate.limit(), Some(900));
+        assert_eq!(rate.remaining(), 899);
+        assert_eq!(rate.window, Some(Duration::minutes(15)));
+        assert_eq!(
+            rate.matched_headers,
+            vec![
+                "X-Rate-Limit-Remaining",
+                "X-Rate-Limit-Limit",
+                "X-Rate-Limit-Reset"
+            ]
+        );
+
+        let rate = Headers::new_with_vendor(headers, Vendor::Twitter).unwrap();
+        assert_eq!(rate.remaining(), 899);
+
+        // Each header is looked up under its main name first
+        let headers = indoc! {"
+            x-rate-limit-limit: 900
+            X-Rate-Limit-Remaining: 899
+            x-rate-limit-remaining: 10
+            X-Rate-Limit-Reset: 1350085394
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Twitter);
+        assert_eq!(rate.remaining(), 10);
+        assert_eq!(
+            rate.matched_headers,
+            vec![
+                "x-rate-limit-remaining",
+                "x-rate-limit-limit",
+                "X-Rate-Limit-Reset"
+            ]
+        );
+
+        let twitter = known_variants()
+            .find(|variant| variant.vendor() == Vendor::Twitter)
+            .unwrap();
+        assert!(twitter.alternate_names().contains(&"X-Rate-Limit-Limit"));
+    }
+
+    #[test]
+    fn parse_standard_reset_seconds_or_timestamp() {
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 99
+            Ratelimit-Reset: 50
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.reset(), ResetTime::Seconds(50));
+
+        // An epoch is not misread as decades worth of seconds
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 99
+            Ratelimit-Reset: 1350085394
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_twitch_headers() {
+        let headers = indoc! {"
+            Ratelimit-Limit: 800
+            Ratelimit-Remaining: 799
+            Ratelimit-Reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Twitch);
+        assert_eq!(rate.limit(), Some(800));
+        assert_eq!(rate.window, Some(Duration::MINUTE));
+        // Not parsed as seconds, like the `Standard` reset header
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 99
+            Ratelimit-Reset: 30
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+    }
+
+    #[test]
+    fn compare_ignoring_vendor() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+        let github = Headers::from_str(headers).unwrap();
+        let algolia = Headers::new_with_vendor(headers, Vendor::Algolia).unwrap();
+        assert_ne!(github, algolia);
+        assert!(github.eq_ignoring_vendor(&algolia));
+
+        let other = Headers {
+            remaining: 4986,
+            ..algolia.clone()
+        };
+        assert!(!github.eq_ignoring_vendor(&other));
+
+        let other = Headers {
+            used: Some(13),
+            ..algolia.clone()
+        };
+        assert!(!github.eq_ignoring_vendor(&other));
+
+        // Limits of different resources aren't the same
+        let core = Headers {
+            resource: Some("core".to_string()),
+            ..github.clone()
+        };
+        let search = Headers {
+            resource: Some("search".to_string()),
+            ..algolia
+        };
+        assert!(!core.eq_ignoring_vendor(&search));
+        assert!(core.eq_ignoring_vendor(&core));
+    }
+
+    #[test]
+    fn safe_rate() {
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 50
+            Ratelimit-Reset: 20
+        "};
+        assert_eq!(Headers::from_str(headers).unwrap().safe_rate(), 2.5);
+
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 50
+            Ratelimit-Reset: 0
+        "};
+        assert_eq!(Headers::from_str(headers).unwrap().safe_rate(), 50.0);
+    }
+
+    #[test]
+    fn near_limit() {
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 15
+            Ratelimit-Reset: 20
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.usage_ratio(), Some(0.85));
+        assert!(rate.is_near_limit(0.8));
+        assert!(!rate.is_near_limit(0.9));
+
+        // An explicit signal takes precedence
+        let headers = indoc! {"
+            X-RateLimit-Limit: 1000
+            X-RateLimit-Remaining: 900
+            X-RateLimit-Reset: 1470173023
+            X-RateLimit-NearLimit: true
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.usage_ratio(), Some(0.1));
+        assert!(rate.is_near_limit(0.8));
+
+        let headers = indoc! {"
+            RateLimit-Remaining: 0
+            RateLimit-Reset: 30
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.usage_ratio(), None);
+        assert!(!rate.is_near_limit(0.8));
+    }
+
+    #[test]
+    fn projected_remaining() {
+        let headers = indoc! {"
+            X-Ratelimit-Used: 500
+            X-Ratelimit-Remaining: 100
+            X-Ratelimit-Reset: 300
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.window, Some(Duration::minutes(10)));
+
+        let now = datetime!(2024-01-01 12:00:00 UTC);
+        let projected = |at| rate.projected_remaining_with_reference(at, now);
+        assert_eq!(projected(now), Some(100));
+        assert_eq!(projected(now - Duration::minutes(1)), Some(100));
+        // 600 requests per 10 minutes refill one request per second
+        assert_eq!(projected(now + Duration::seconds(30)), Some(130));
+        assert_eq!(projected(now + Duration::minutes(4)), Some(340));
+        // The quota is restored once the rate limit resets
+        assert_eq!(projected(now + Duration::minutes(5)), Some(600));
+        assert_eq!(projected(now + Duration::hours(1)), Some(600));
+
+        // The window is unknown
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            RateLimit-Remaining: 15
+            RateLimit-Reset: 20
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.window, None);
+        assert_eq!(rate.projected_remaining(OffsetDateTime::now_utc()), None);
+    }
+
+    #[test]
+    fn estimate_window_from_reset_seconds() {
+        let headers = indoc! {"
+            Fastly-RateLimit-Limit: 100
+            Fastly-RateLimit-Remaining: 42
+            Fastly-RateLimit-Reset: 17
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.window, None);
+        assert_eq!(rate.estimated_window(), Some(Duration::seconds(17)));
+
+        // No request was made yet, so nothing can be inferred
+        let headers = indoc! {"
+            Fastly-RateLimit-Limit: 100
+            Fastly-RateLimit-Remaining: 100
+            Fastly-RateLimit-Reset: 17
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.estimated_window(), None);
+
+        // A known window takes precedence
+        let headers = indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.estimated_window(), Some(Duration::minutes(10)));
+    }
+
+    #[test]
+    fn parse_vimeo_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 100
+            X-RateLimit-Remaining: 99
+            X-RateLimit-Reset: Tue, 15 Nov 1994 08:12:31 GMT
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Vimeo);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(1994-11-15 08:12:31 UTC))
+        );
+    }
+
+    #[test]
+    fn parse_jira_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 350
+            X-RateLimit-Remaining: 349
+            X-RateLimit-Reset: 2023-04-01T12:00:00Z
+            X-RateLimit-Interval-Seconds: 10
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Jira);
+        assert_eq!(rate.window, Some(Duration::seconds(10)));
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2023-04-01 12:00:00 UTC))
+        );
+        assert_eq!(
+            rate.matched_headers,
+            vec![
+                "X-RateLimit-Remaining",
+                "X-RateLimit-Limit",
+                "X-RateLimit-Reset",
+                "X-RateLimit-Interval-Seconds"
+            ]
+        );
+
+        // Without the interval header, the headers are Mastodon's
+        let headers = indoc! {"
+            X-RateLimit-Limit: 350
+            X-RateLimit-Remaining: 349
+            X-RateLimit-Reset: 2023-04-01T12:00:00Z
+        "};
+        assert_eq!(Headers::from_str(headers).unwrap().vendor, Vendor::Mastodon);
+    }
+
+    #[test]
+    fn window_header_overrides_duration() {
+        static VARIANTS: &[RateLimitVariant] = &[RateLimitVariant::new(
+            Vendor::Standard,
+            Some(Duration::HOUR),
+            Some("Limit"),
+            None,
+            "Remaining",
+            &[("Reset", ResetTimeKind::Seconds)],
+        )
+        .with_window_header("Window")];
+
+        let map = CaseSensitiveHeaderMap::from_str(indoc! {"
+            Limit: 100
+            Remaining: 99
+            Reset: 30
+            Window: 60
+        "})
+        .unwrap();
+        let rate = Headers::from_variants(&map, VARIANTS).unwrap();
+        assert_eq!(rate.window, Some(Duration::MINUTE));
+
+        let map = CaseSensitiveHeaderMap::from_str(indoc! {"
+            Limit: 100
+            Remaining: 99
+            Reset: 30
+        "})
+        .unwrap();
+        let rate = Headers::from_variants(&map, VARIANTS).unwrap();
+        assert_eq!(rate.window, Some(Duration::HOUR));
+
+        let map = CaseSensitiveHeaderMap::from_str(indoc! {"
+            Limit: 100
+            Remaining: 99
+            Reset: 30
+            Window: soon
+        "})
+        .unwrap();
+        assert!(matches!(
+            Headers::from_variants(&map, VARIANTS),
+            Err(Error::InvalidValueFor { header, .. }) if header == "Window"
+        ));
+    }
+
+    #[test]
+    fn parse_mastodon_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 300
+            X-RateLimit-Remaining: 299
+            X-RateLimit-Reset: 2023-01-01T00:00:00.000000Z
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Mastodon);
+        assert_eq!(rate.limit(), Some(300));
+        assert_eq!(rate.remaining(), 299);
+        assert_eq!(rate.window, Some(Duration::minutes(5)));
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2023-01-01 00:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn parse_square_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 100
+            X-RateLimit-Remaining: 42
+            X-RateLimit-Reset: 2023-03-15T18:24:05.123Z
+        "};
+
+        // Without a hint, the headers are Mastodon's
+        assert_eq!(Headers::from_str(headers).unwrap().vendor, Vendor::Mastodon);
+        assert!(
+            Headers::check_ambiguity(&CaseSensitiveHeaderMap::from_str(headers).unwrap()).is_ok()
+        );
+
+        let rate = Headers::new_with_vendor(headers, Vendor::Square).unwrap();
+        assert_eq!(rate.vendor, Vendor::Square);
+        assert_eq!(rate.limit(), Some(100));
+        assert_eq!(rate.remaining(), 42);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2023-03-15 18:24:05.123 UTC))
+        );
+
+        // The offset is respected
+        let headers = indoc! {"
+            X-RateLimit-Limit: 100
+            X-RateLimit-Remaining: 42
+            X-RateLimit-Reset: 2023-03-15T11:24:05.123-07:00
+        "};
+        let rate = Headers::new_with_vendor(headers, Vendor::Square).unwrap();
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2023-03-15 18:24:05.123 UTC))
+        );
+
+        // Without a vendor hint, the headers are Mastodon's
+        assert_eq!(Headers::from_str(headers).unwrap().vendor, Vendor::Mastodon);
+    }
+
+    #[test]
+    fn parse_discord_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 5
+            X-RateLimit-Remaining: 0
+            X-RateLimit-Reset: 1470173023.123
+            X-RateLimit-Reset-After: 1.2
+            X-RateLimit-Bucket: abcd1234
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Discord);
+        assert_eq!(rate.limit(), Some(5));
+        assert_eq!(rate.remaining(), 0);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2016-08-02 21:23:44 UTC))
+        );
+        assert_eq!(rate.reset_after, Some(Duration::seconds(2)));
+        assert_eq!(rate.effective_reset(), ResetTime::Seconds(2));
+        assert_eq!(rate.resource.as_deref(), Some("abcd1234"));
+        assert_eq!(rate.near_limit, None);
+    }
+
+    #[test]
+    fn parse_bitbucket_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 1000
+            X-RateLimit-Remaining: 150
+            X-RateLimit-Reset: 1470173023
+            X-RateLimit-NearLimit: true
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Bitbucket);
+        assert_eq!(rate.limit(), Some(1000));
+        assert_eq!(rate.remaining(), 150);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2016-08-02 21:23:43 UTC))
+        );
+        assert_eq!(rate.near_limit, Some(true));
+        assert!(rate
+            .matched_headers
+            .iter()
+            .any(|name| name == "X-RateLimit-NearLimit"));
+
+        assert!(Headers::from_str(&headers.replace("true", "maybe")).is_err());
+
+        // Without the near-limit header, the headers are Discord's
+        let headers = indoc! {"
+            X-RateLimit-Limit: 1000
+            X-RateLimit-Remaining: 150
+            X-RateLimit-Reset: 1470173023
+        "};
+        assert_eq!(Headers::from_str(headers).unwrap().vendor, Vendor::Discord);
+    }
+
+    #[test]
+    fn effective_reset_without_reset_after() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.reset_after, None);
+        assert_eq!(rate.effective_reset(), rate.reset());
+    }
+
+    #[test]
+    fn parse_sendgrid_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 600
+            X-RateLimit-Remaining: 599
+            X-RateLimit-Reset: 1350085394
+        "};
+
+        let rate = Headers::new_with_vendor(headers, Vendor::SendGrid).unwrap();
+        assert_eq!(rate.vendor, Vendor::SendGrid);
+        assert_eq!(rate.limit(), Some(600));
+        assert_eq!(rate.remaining(), 599);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2012-10-12 23:43:14 UTC))
+        );
+        assert_eq!(rate.window, Some(Duration::minutes(1)));
+    }
+
+    #[test]
+    fn vendor_without_rate_limit_headers() {
+        assert!(Headers::variants_of(Vendor::Notion).is_empty());
+        assert!(matches!(
+            Headers::new_with_vendor("Retry-After: 10", Vendor::Notion),
+            Err(Error::MissingRemaining { candidates }) if candidates.is_empty()
+        ));
+        assert!(known_variants().all(|variant| {
+            Headers::variants_of(variant.vendor())
+                .iter()
+                .all(|other| other.vendor() == variant.vendor())
+        }));
+    }
+
+    #[test]
+    fn parse_structured_header() {
+        let headers = Headers::from_str("RateLimit: limit=100, remaining=42, reset=30").unwrap();
+        assert_eq!(headers.limit, Some(100));
+        assert_eq!(headers.remaining, 42);
+        assert_eq!(headers.reset, ResetTime::Seconds(30));
+        assert_eq!(headers.vendor, Vendor::Standard);
+
+        let headers =
+            Headers::new_with_vendor("ratelimit: remaining=42, reset=30", Vendor::Standard)
+                .unwrap();
+        assert_eq!(headers.remaining, 42);
+        assert!(
+            Headers::new_with_vendor("RateLimit: remaining=42, reset=30", Vendor::Github).is_err()
+        );
+
+        let error = Headers::from_str("ratelimit: limit=100").unwrap_err();
+        assert!(matches!(error, Error::MissingRemaining { .. }));
+        assert!(error.is_missing());
+        assert!(matches!(
+            Headers::from_str("ratelimit: remaining=many, reset=30"),
+            Err(Error::InvalidValueFor { header, .. }) if header == "ratelimit"
+        ));
+        assert!(Headers::contains_any(
+            &CaseSensitiveHeaderMap::from_str("RateLimit: limit=100").unwrap()
+        ));
+    }
+
+    #[test]
+    fn parse_unknown_vendor_headers() {
+        let headers = Headers::from_str(indoc! {"
+            Acme-RateLimit-Limit: 100
+            Acme-RateLimit-Remaining: 42
+            Acme-RateLimit-Reset: 30
+        "})
+        .unwrap();
+        assert_eq!(headers.limit, Some(100));
+        assert_eq!(headers.remaining, 42);
+        assert_eq!(headers.reset, ResetTime::Seconds(30));
+        assert_eq!(headers.vendor, Vendor::Standard);
+        assert_eq!(
+            headers.matched_headers,
+            vec![
+                "Acme-RateLimit-Remaining",
+                "Acme-RateLimit-Limit",
+                "Acme-RateLimit-Reset"
+            ]
+        );
+
+        // Known variants are checked first
+        let headers = Headers::from_str(indoc! {"
+            Acme-RateLimit-Remaining: 42
+            Acme-RateLimit-Reset: 30
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(headers.vendor, Vendor::Github);
+
+        // The fallback is not used if the vendor is known
+        assert!(Headers::new_with_vendor(
+            "Acme-RateLimit-Remaining: 42\nAcme-RateLimit-Reset: 30",
+            Vendor::Standard
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_fastly_headers() {
+        let headers = indoc! {"
+            Fastly-RateLimit-Limit: 100
+            Fastly-RateLimit-Remaining: 42
+            Fastly-RateLimit-Reset: 17
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), Some(100));
+        assert_eq!(rate.remaining(), 42);
+        assert_eq!(rate.reset(), ResetTime::Seconds(17));
+        assert_eq!(rate.vendor, Vendor::Fastly);
+    }
+
+    #[test]
+    fn parse_multiple_reset_headers() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 0
+            X-RateLimit-Reset: Fri, 01 Jan 2100 00:00:00 GMT
+            X-RateLimit-Next: 2099-01-01T00:00:00
+        "};
+
+        let candidates = Headers::reset_candidates(headers).unwrap();
+        assert_eq!(
+            candidates,
+            vec![
+                ResetTime::DateTime(datetime!(2100-01-01 0:00:00 UTC)),
+                ResetTime::DateTime(datetime!(2099-01-01 0:00:00 UTC)),
+            ]
+        );
+
+        // All headers of Vimeo are present, so Akamai's reset header is ignored
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Vimeo);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2100-01-01 0:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn parse_fields_of_matched_variant() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+            X-Ratelimit-Reset: 30
+        "};
+
+        // Reddit's reset header is not mixed into Github's headers
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Github);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+        assert!(!rate
+            .matched_headers
+            .iter()
+            .any(|name| name == "X-Ratelimit-Reset"));
+    }
+
+    #[test]
+    fn parse_coherent_variant_with_partial_matches() {
+        // Vimeo's headers plus stray `Standard` and Github headers
+        let headers = indoc! {"
+            Ratelimit-Remaining: 1
+            x-ratelimit-limit: 5000
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: Tue, 05 Jan 2021 11:00:00 GMT
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Vimeo);
+        assert_eq!(rate.limit(), Some(60));
+        assert_eq!(rate.remaining(), 59);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2021-01-05 11:00:00 UTC))
+        );
+        assert_eq!(rate.window, Some(Duration::seconds(60)));
+        assert_eq!(
+            rate.matched_headers,
+            vec![
+                "X-RateLimit-Remaining",
+                "X-RateLimit-Limit",
+                "X-RateLimit-Reset"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_gitlab_reset_time_header() {
+        let headers = indoc! {"
+            RateLimit-Limit: 60
+            RateLimit-Observed: 67
+            RateLimit-Remaining: 0
+            RateLimit-ResetTime: Tue, 05 Jan 2021 11:00:00 GMT
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), Some(60));
+        assert_eq!(rate.remaining(), 0);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2021-01-05 11:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn parse_gitlab_over_limit() {
+        let headers = indoc! {"
+            RateLimit-Limit: 60
+            RateLimit-Observed: 67
+            RateLimit-Remaining: 5
+            RateLimit-Reset: 1609844400
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Gitlab);
+        assert_eq!(rate.used, Some(67));
+        assert_eq!(rate.remaining(), 0);
+        assert!(rate.over_limit());
+
+        let headers = indoc! {"
+            RateLimit-Limit: 60
+            RateLimit-Observed: 55
+            RateLimit-Remaining: 5
+            RateLimit-Reset: 1609844400
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.remaining(), 5);
+        assert!(!rate.over_limit());
+    }
+
+    #[test]
+    fn parse_microsoft_graph_headers() {
+        let headers = indoc! {"
+            RateLimit-Limit: 1000
+            RateLimit-Remaining: 0
+            RateLimit-Reset: 30
+            x-ms-throttle-scope: Tenant_Application/ReadWrite/9a3f5c6e/1b2c3d4e
+            x-ms-throttle-information: ResourceUnitLimitExceeded
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::MicrosoftGraph);
+        assert_eq!(rate.limit(), Some(1000));
+        assert_eq!(rate.remaining(), 0);
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+        assert_eq!(
+            rate.throttle_scope.as_deref(),
+            Some("Tenant_Application/ReadWrite/9a3f5c6e/1b2c3d4e")
+        );
+
+        // Only one of the `x-ms-throttle-*` headers is needed
+        let headers = indoc! {"
+            RateLimit-Limit: 1000
+            RateLimit-Remaining: 0
+            RateLimit-Reset: 30
+            x-ms-throttle-information: ResourceUnitLimitExceeded
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::MicrosoftGraph);
+        assert_eq!(rate.throttle_scope, None);
+
+        // Without them, these are the draft's headers
+        let headers = indoc! {"
+            RateLimit-Limit: 1000
+            RateLimit-Remaining: 999
+            RateLimit-Reset: 30
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(rate.limit(), Some(1000));
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+
+        let headers = indoc! {"
+            RateLimit-Limit: 1000
+            RateLimit-Remaining: 999
+            RateLimit-Reset: 1900000000
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Standard);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_900_000_000).unwrap())
+        );
+        assert!(
+            Headers::check_ambiguity(&CaseSensitiveHeaderMap::from_str(headers).unwrap()).is_ok()
+        );
+    }
+
+    #[test]
+    fn parse_github_resource() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 30
+            x-ratelimit-remaining: 29
+            x-ratelimit-reset: 1350085394
+            x-ratelimit-resource: search
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Github);
+        assert_eq!(rate.resource.as_deref(), Some("search"));
+        assert!(rate
+            .matched_headers
+            .iter()
+            .any(|name| name == "x-ratelimit-resource"));
+
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.resource, None);
+    }
+
+    #[test]
+    fn parse_heroku_headers() {
+        let headers = indoc! {"
+            RateLimit-Remaining: 4500
+            RateLimit-Reset: 30
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), None);
+        assert_eq!(rate.remaining(), 4500);
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+        assert_eq!(rate.vendor, Vendor::Heroku);
+    }
+
+    #[test]
+    fn parse_openai_headers() {
+        let headers = indoc! {"
+            x-ratelimit-limit-requests: 60
+            x-ratelimit-limit-tokens: 150000
+            x-ratelimit-remaining-requests: 59
+            x-ratelimit-remaining-tokens: 149984
+            x-ratelimit-reset-requests: 6m0s
+            x-ratelimit-reset-tokens: 1.5s
+        "};
+
+        let requests = Headers::from_str(headers).unwrap();
+        assert_eq!(requests.vendor, Vendor::OpenAI);
+        assert_eq!(requests.limit(), Some(60));
+        assert_eq!(requests.remaining(), 59);
+        assert_eq!(requests.reset(), ResetTime::Seconds(360));
+
+        let tokens = Headers::tokens(CaseSensitiveHeaderMap::from_str(headers).unwrap()).unwrap();
+        assert_eq!(tokens.vendor, Vendor::OpenAI);
+        assert_eq!(tokens.limit(), Some(150_000));
+        assert_eq!(tokens.remaining(), 149_984);
+        assert_eq!(tokens.reset(), ResetTime::Seconds(2));
+    }
+
+    #[test]
+    fn parse_pinterest_headers() {
+        let headers = indoc! {"
+            X-Ratelimit-Limit: 1000
+            X-Ratelimit-Used-Percent: 12.5
+            X-Ratelimit-Reset: 60
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.vendor, Vendor::Pinterest);
+        assert_eq!(rate.limit(), Some(1000));
+        assert_eq!(rate.remaining(), 875);
+        assert_eq!(rate.reset(), ResetTime::Seconds(60));
+
+        // Partially used requests count as used
+        let headers = indoc! {"
+            X-Ratelimit-Limit: 10
+            X-Ratelimit-Used-Percent: 33.3
+            X-Ratelimit-Reset: 60
+        "};
+        assert_eq!(Headers::from_str(headers).unwrap().remaining(), 6);
+
+        let headers = indoc! {"
+            X-Ratelimit-Used-Percent: 12.5
+            X-Ratelimit-Reset: 60
+        "};
+        assert!(matches!(
+            Headers::from_str(headers),
+            Err(Error::MissingLimit { .. })
+        ));
+
+        let headers = indoc! {"
+            X-Ratelimit-Limit: 1000
+            X-Ratelimit-Used-Percent: 120
+            X-Ratelimit-Reset: 60
+        "};
+        assert!(matches!(
+            Headers::from_str(headers),
+            Err(Error::InvalidValueFor { header, .. }) if header == "X-Ratelimit-Used-Percent"
+        ));
+    }
+
+    #[test]
+    fn parse_headers_without_limit() {
+        let headers = indoc! {"
+            x-ratelimit-remaining: 10
+            x-ratelimit-reset: 1350085394
+        "};
+
+        let rate = Headers::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), None);
+        assert_eq!(rate.remaining(), 10);
+        assert_eq!(rate.vendor, Vendor::Github);
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1_350_085_394).unwrap())
+        );
+    }
+
     #[test]
     fn parse_gitlab_headers() {
         let headers = indoc! {"
@@ -301,7 +2446,8 @@ x-ratelimit-reset: 1350085394
         "};
 
         let rate = Headers::from_str(headers).unwrap();
-        assert_eq!(rate.limit(), 60);
+        assert_eq!(rate.vendor, Vendor::Gitlab);
+        assert_eq!(rate.limit(), Some(60));
         assert_eq!(rate.remaining(), 0);
         assert_eq!(
             rate.reset(),
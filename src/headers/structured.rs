@@ -0,0 +1,110 @@
+//! Rate limit headers as a structured field dictionary ([RFC 8941])
+//!
+//! Newer drafts of the rate limit headers standard combine all fields into
+//! a single header, e.g. `RateLimit: limit=100, remaining=42, reset=30`.
+//!
+//! [RFC 8941]: https://www.rfc-editor.org/rfc/rfc8941.html#name-dictionaries
+use std::borrow::Cow;
+
+use headers::HeaderValue;
+
+use crate::casesensitive_headermap::CaseSensitiveHeaderMap;
+use crate::error::{Error, Result};
+use crate::reset_time::{ResetTime, ResetTimeKind};
+
+use super::{Headers, Limit, Remaining, Vendor};
+
+/// Accepted spellings of the structured rate limit header
+pub(crate) const STRUCTURED_HEADERS: [&str; 2] = ["RateLimit", "ratelimit"];
+
+/// Get the structured rate limit header from the given header map
+pub(crate) fn get(header_map: &CaseSensitiveHeaderMap) -> Option<(&'static str, &HeaderValue)> {
+    STRUCTURED_HEADERS
+        .iter()
+        .find_map(|&name| Some((name, header_map.get(name)?)))
+}
+
+/// Parse a structured field dictionary into rate limits
+///
+/// The `remaining` and `reset` keys are required, `limit` is optional.
+/// The reset time is given in seconds. Parameters (e.g. `;w=60`) and
+/// unknown keys are ignored. If a key appears more than once, the last
+/// value wins, as required by RFC 8941.
+///
+/// Missing keys are reported like missing headers (e.g.
+/// [`Error::MissingRemaining`]), with the `RateLimit` header as candidate.
+pub(crate) fn parse(value: &str) -> Result<Headers> {
+    let candidates = || vec![STRUCTURED_HEADERS[0].to_string()];
+
+    let mut limit = None;
+    let mut remaining = None;
+    let mut reset = None;
+    for member in value.split(',') {
+        let member = member.split(';').next().unwrap_or(member).trim();
+        let Some((key, value)) = member.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "limit" => limit = Some(Limit::new(value)?.count),
+            "remaining" => remaining = Some(Remaining::new(value)?.count),
+            "reset" => reset = Some(ResetTime::parse(value, ResetTimeKind::Seconds)?),
+            _ => {}
+        }
+    }
+
+    Ok(Headers {
+        limit,
+        remaining: remaining.ok_or_else(|| Error::MissingRemaining {
+            candidates: candidates(),
+        })?,
+        used: None,
+        reset: reset.ok_or_else(|| Error::MissingReset {
+            candidates: candidates(),
+        })?,
+        window: None,
+        vendor: Vendor::Standard,
+        reset_after: None,
+        throttle_scope: None,
+        resource: None,
+        near_limit: None,
+        matched_headers: vec![Cow::Borrowed(STRUCTURED_HEADERS[0])],
+        other_limits: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dictionary() {
+        let headers = parse("limit=100, remaining=42, reset=30").unwrap();
+        assert_eq!(headers.limit, Some(100));
+        assert_eq!(headers.remaining, 42);
+        assert_eq!(headers.reset, ResetTime::Seconds(30));
+        assert_eq!(headers.vendor, Vendor::Standard);
+    }
+
+    #[test]
+    fn parse_dictionary_lenient() {
+        let headers =
+            parse("remaining=42;w=60,reset=30, policy=\"default\", remaining=41").unwrap();
+        assert_eq!(headers.limit, None);
+        assert_eq!(headers.remaining, 41);
+        assert_eq!(headers.reset, ResetTime::Seconds(30));
+    }
+
+    #[test]
+    fn parse_incomplete_dictionary() {
+        assert!(matches!(
+            parse("limit=100, remaining=42"),
+            Err(Error::MissingReset { candidates }) if candidates == ["RateLimit"]
+        ));
+        assert!(matches!(
+            parse("limit=100, reset=30"),
+            Err(Error::MissingRemaining { candidates }) if candidates == ["RateLimit"]
+        ));
+        assert!(parse("limit=100, remaining=many, reset=30").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Typed header support for the [`headers`] crate
+//!
+//! The `headers` crate (and `TypedHeader` in axum) works with one header at
+//! a time, so only the structured `RateLimit` header, which combines all
+//! fields, can be represented this way.
+use headers::{Header, HeaderName, HeaderValue};
+
+use super::{structured, Headers};
+
+/// Lowercase name of the structured rate limit header
+static RATE_LIMIT: HeaderName = HeaderName::from_static("ratelimit");
+
+/// The structured `RateLimit` header as a typed header,
+/// e.g. `RateLimit: limit=100, remaining=42, reset=30`
+///
+/// This implements [`headers::Header`], so it can be extracted with
+/// [`headers::HeaderMapExt::typed_get`] or axum's `TypedHeader`.
+/// See [`Headers::from_structured`] for the accepted format.
+///
+/// Encoding is lossy: only the limit, the remaining requests and the reset
+/// time are sent, and the reset time is always sent in seconds. So an
+/// absolute reset time ([`ResetTime::DateTime`]) is decoded as the number
+/// of seconds from the time of encoding until then.
+///
+/// [`ResetTime::DateTime`]: crate::ResetTime::DateTime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitHeader(pub Headers);
+
+impl RateLimitHeader {
+    /// Get the parsed rate limit headers
+    #[must_use]
+    pub fn into_inner(self) -> Headers {
+        self.0
+    }
+}
+
+impl From<RateLimitHeader> for Headers {
+    fn from(header: RateLimitHeader) -> Self {
+        header.0
+    }
+}
+
+impl Header for RateLimitHeader {
+    fn name() -> &'static HeaderName {
+        &RATE_LIMIT
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        let value = values.next().ok_or_else(headers::Error::invalid)?;
+        let value = value.to_str().map_err(|_| headers::Error::invalid())?;
+        structured::parse(value)
+            .map(RateLimitHeader)
+            .map_err(|_| headers::Error::invalid())
+    }
+
+    /// Encode the rate limits as a structured field dictionary
+    ///
+    /// The reset time is always sent in seconds, relative to the time of
+    /// encoding. Absolute reset times don't survive a roundtrip.
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        let headers = &self.0;
+        let mut value = String::new();
+        if let Some(limit) = headers.limit {
+            value.push_str(&format!("limit={limit}, "));
+        }
+        value.push_str(&format!(
+            "remaining={}, reset={}",
+            headers.remaining,
+            headers.effective_reset().seconds()
+        ));
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            values.extend(std::iter::once(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reset_time::ResetTime;
+    use headers::{HeaderMap, HeaderMapExt};
+    use time::{Duration, OffsetDateTime};
+
+    #[test]
+    fn typed_get() {
+        let mut map = HeaderMap::new();
+        map.insert(
+            "RateLimit",
+            HeaderValue::from_static("limit=100, remaining=42, reset=30"),
+        );
+
+        let header = map.typed_get::<RateLimitHeader>().unwrap();
+        assert_eq!(header.0.limit, Some(100));
+        assert_eq!(header.0.remaining, 42);
+        assert_eq!(header.0.reset, ResetTime::Seconds(30));
+
+        map.insert("RateLimit", HeaderValue::from_static("remaining=many"));
+        assert!(map.typed_try_get::<RateLimitHeader>().is_err());
+    }
+
+    #[test]
+    fn typed_insert_roundtrip() {
+        let headers = Headers::from_structured("limit=100, remaining=42, reset=30").unwrap();
+        let mut map = HeaderMap::new();
+        map.typed_insert(RateLimitHeader(headers.clone()));
+
+        assert_eq!(map["ratelimit"], "limit=100, remaining=42, reset=30");
+        assert_eq!(
+            map.typed_get::<RateLimitHeader>().unwrap().into_inner(),
+            headers
+        );
+    }
+
+    #[test]
+    fn typed_insert_roundtrip_datetime() {
+        let reset = OffsetDateTime::now_utc() + Duration::hours(1);
+        let headers = Headers {
+            reset: ResetTime::DateTime(reset),
+            ..Headers::from_structured("limit=100, remaining=42, reset=30").unwrap()
+        };
+        let mut map = HeaderMap::new();
+        map.typed_insert(RateLimitHeader(headers.clone()));
+
+        // The absolute reset time is sent as seconds from now
+        let decoded = map.typed_get::<RateLimitHeader>().unwrap().into_inner();
+        assert_ne!(decoded, headers);
+        assert_eq!(decoded.limit, Some(100));
+        assert_eq!(decoded.remaining, 42);
+        assert!(matches!(decoded.reset, ResetTime::Seconds(3599..=3600)));
+    }
+}
@@ -1,13 +1,18 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::casesensitive_headermap::CaseSensitiveHeaderMap;
 use crate::convert;
-use crate::error::Result;
-use crate::reset_time::ResetTimeKind;
+use crate::error::{Error, Result};
+use crate::reset_time::{ResetTime, ResetTimeKind};
+use headers::HeaderValue;
 use time::Duration;
 
 /// Known vendors of rate limit headers
 ///
 /// Vendors use different rate limit header formats,
 /// which define how to parse them.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Vendor {
     /// Rate limit headers as defined in the `polli-ratelimit-headers-00` draft
     Standard,
@@ -15,33 +20,190 @@ pub enum Vendor {
     Reddit,
     /// Github API rate limit headers
     Github,
+    /// Algolia search API rate limit headers
+    ///
+    /// They are the same as Github's, so they are only used with a vendor hint.
+    Algolia,
     /// Twitter API rate limit headers
     Twitter,
+    /// Twitch Helix API rate limit headers
+    Twitch,
     /// Vimeo rate limit headers
     Vimeo,
+    /// Jira Cloud (and other Atlassian products) rate limit headers
+    Jira,
+    /// Mastodon (and other Fediverse servers) rate limit headers
+    Mastodon,
+    /// Square API rate limit headers
+    ///
+    /// They are the same as Mastodon's, so they are only used with a vendor hint.
+    Square,
+    /// Bitbucket Cloud rate limit headers
+    Bitbucket,
+    /// Discord API rate limit headers
+    Discord,
+    /// SendGrid API rate limit headers
+    ///
+    /// They are the same as Discord's, so they are only used with a vendor hint.
+    SendGrid,
     /// Gitlab rate limit headers
     Gitlab,
+    /// Microsoft Graph API rate limit headers
+    MicrosoftGraph,
     /// Akamai rate limit headers
     Akamai,
+    /// Fastly rate limit headers
+    Fastly,
+    /// Heroku Platform API rate limit headers
+    Heroku,
+    /// OpenAI API rate limit headers
+    OpenAI,
+    /// Pinterest API rate limit headers
+    Pinterest,
+    /// Notion API, which only sends a `Retry-After` header
+    Notion,
+}
+
+impl Vendor {
+    /// Canonical lowercase name of the vendor
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Vendor::Standard => "standard",
+            Vendor::Reddit => "reddit",
+            Vendor::Github => "github",
+            Vendor::Algolia => "algolia",
+            Vendor::Twitter => "twitter",
+            Vendor::Twitch => "twitch",
+            Vendor::Vimeo => "vimeo",
+            Vendor::Jira => "jira",
+            Vendor::Mastodon => "mastodon",
+            Vendor::Square => "square",
+            Vendor::Bitbucket => "bitbucket",
+            Vendor::Discord => "discord",
+            Vendor::SendGrid => "sendgrid",
+            Vendor::Gitlab => "gitlab",
+            Vendor::MicrosoftGraph => "microsoftgraph",
+            Vendor::Akamai => "akamai",
+            Vendor::Fastly => "fastly",
+            Vendor::Heroku => "heroku",
+            Vendor::OpenAI => "openai",
+            Vendor::Pinterest => "pinterest",
+            Vendor::Notion => "notion",
+        }
+    }
+
+    /// Link to the vendor's official documentation of its rate limits
+    ///
+    /// This is useful for error messages. Returns `None` for
+    /// [`Vendor::Standard`], which is not a vendor.
+    #[must_use]
+    pub const fn docs_url(&self) -> Option<&'static str> {
+        let url = match self {
+            Vendor::Standard => return None,
+            Vendor::Reddit => "https://www.reddit.com/r/redditdev/comments/1yxrp7/formal_ratelimiting_headers/",
+            Vendor::Github => "https://docs.github.com/en/rest/overview/resources-in-the-rest-api#rate-limit-http-headers",
+            Vendor::Algolia => "https://www.algolia.com/doc/guides/security/api-keys/how-to/rate-limit-api-keys/",
+            Vendor::Twitter => "https://developer.twitter.com/en/docs/twitter-api/rate-limits",
+            Vendor::Twitch => "https://dev.twitch.tv/docs/api/guide/#twitch-rate-limits",
+            Vendor::Vimeo => "https://developer.vimeo.com/guidelines/rate-limiting",
+            Vendor::Jira => "https://developer.atlassian.com/cloud/jira/platform/rate-limiting/",
+            Vendor::Mastodon => "https://docs.joinmastodon.org/api/rate-limits/",
+            Vendor::Square => "https://developer.squareup.com/docs/build-basics/general-considerations/handling-errors",
+            Vendor::Bitbucket => "https://support.atlassian.com/bitbucket-cloud/docs/api-request-limits/",
+            Vendor::Discord => "https://discord.com/developers/docs/topics/rate-limits#header-format",
+            Vendor::SendGrid => "https://docs.sendgrid.com/api-reference/how-to-use-the-sendgrid-v3-api/rate-limits",
+            Vendor::Gitlab => "https://docs.gitlab.com/ee/user/admin_area/settings/user_and_ip_rate_limits.html#response-headers",
+            Vendor::MicrosoftGraph => "https://learn.microsoft.com/en-us/graph/throttling",
+            Vendor::Akamai => "https://techdocs.akamai.com/adaptive-media-delivery/reference/rate-limiting",
+            Vendor::Fastly => "https://docs.fastly.com/en/guides/working-with-rate-limiting-policies",
+            Vendor::Heroku => "https://devcenter.heroku.com/articles/platform-api-reference#rate-limits",
+            Vendor::OpenAI => "https://platform.openai.com/docs/guides/rate-limits",
+            Vendor::Pinterest => "https://developers.pinterest.com/docs/reference/ratelimits/",
+            Vendor::Notion => "https://developers.notion.com/reference/request-limits",
+        };
+        Some(url)
+    }
+}
+
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Vendor {
+    type Err = Error;
+
+    /// Parse a vendor from its name. The comparison is case-insensitive.
+    fn from_str(name: &str) -> Result<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "standard" => Ok(Vendor::Standard),
+            "reddit" => Ok(Vendor::Reddit),
+            "github" => Ok(Vendor::Github),
+            "algolia" => Ok(Vendor::Algolia),
+            "twitter" => Ok(Vendor::Twitter),
+            "twitch" => Ok(Vendor::Twitch),
+            "vimeo" => Ok(Vendor::Vimeo),
+            "jira" => Ok(Vendor::Jira),
+            "mastodon" => Ok(Vendor::Mastodon),
+            "square" => Ok(Vendor::Square),
+            "bitbucket" => Ok(Vendor::Bitbucket),
+            "discord" => Ok(Vendor::Discord),
+            "sendgrid" => Ok(Vendor::SendGrid),
+            "gitlab" => Ok(Vendor::Gitlab),
+            "microsoftgraph" => Ok(Vendor::MicrosoftGraph),
+            "akamai" => Ok(Vendor::Akamai),
+            "fastly" => Ok(Vendor::Fastly),
+            "heroku" => Ok(Vendor::Heroku),
+            "openai" => Ok(Vendor::OpenAI),
+            "pinterest" => Ok(Vendor::Pinterest),
+            "notion" => Ok(Vendor::Notion),
+            _ => Err(Error::UnknownVendor(name.to_string())),
+        }
+    }
 }
 
 /// A variant defines all relevant fields for parsing headers from a given vendor
-#[derive(Clone, Debug, PartialEq)]
-pub(crate) struct RateLimitVariant {
+///
+/// See [`known_variants`](crate::headers::known_variants) for the list of
+/// built-in variants.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateLimitVariant {
     /// Vendor of the rate limit headers (e.g. Github, Twitter, etc.)
     pub(crate) vendor: Vendor,
     /// Duration of the rate limit interval
     pub(crate) duration: Option<Duration>,
     /// Header name for the maximum number of requests
-    pub(crate) limit_header: Option<String>,
+    pub(crate) limit_header: Option<&'static str>,
     /// Header name for the number of used requests
-    pub(crate) used_header: Option<String>,
+    pub(crate) used_header: Option<&'static str>,
     /// Header name for the number of remaining requests
-    pub(crate) remaining_header: String,
-    /// Header name for the reset time
-    pub(crate) reset_header: String,
-    /// Kind of reset time
-    pub(crate) reset_kind: ResetTimeKind,
+    pub(crate) remaining_header: &'static str,
+    /// Header names for the reset time and their kind of reset time,
+    /// in the order in which they are tried
+    pub(crate) reset_headers: &'static [(&'static str, ResetTimeKind)],
+    /// Header name for the number of seconds until reset,
+    /// sent in addition to an absolute reset time
+    pub(crate) reset_after_header: Option<&'static str>,
+    /// Whether the remaining header holds the percentage of the limit
+    /// which was used, rather than the number of remaining requests
+    pub(crate) remaining_percent_used: bool,
+    /// Header name for the duration of the rate limit interval in seconds,
+    /// which takes precedence over the fixed duration
+    pub(crate) window_header: Option<&'static str>,
+    /// Header name for the scope of the rate limit
+    pub(crate) throttle_scope_header: Option<&'static str>,
+    /// Header name for the resource (bucket) which the rate limit applies to
+    pub(crate) resource_header: Option<&'static str>,
+    /// Header name for whether the client is close to the limit
+    pub(crate) near_limit_header: Option<&'static str>,
+    /// Alternative spellings of the header names above, which are tried
+    /// if the header is not found under its main name
+    pub(crate) alternate_names: &'static [&'static str],
+    /// Header names of which at least one must be present for the variant
+    /// to match
+    pub(crate) marker_headers: &'static [&'static str],
 }
 
 impl RateLimitVariant {
@@ -50,11 +212,10 @@ impl RateLimitVariant {
     pub(crate) const fn new(
         vendor: Vendor,
         duration: Option<Duration>,
-        limit_header: Option<String>,
-        used_header: Option<String>,
-        remaining_header: String,
-        reset_header: String,
-        reset_kind: ResetTimeKind,
+        limit_header: Option<&'static str>,
+        used_header: Option<&'static str>,
+        remaining_header: &'static str,
+        reset_headers: &'static [(&'static str, ResetTimeKind)],
     ) -> Self {
         Self {
             vendor,
@@ -62,10 +223,301 @@ impl RateLimitVariant {
             limit_header,
             used_header,
             remaining_header,
-            reset_header,
-            reset_kind,
+            reset_headers,
+            reset_after_header: None,
+            remaining_percent_used: false,
+            window_header: None,
+            throttle_scope_header: None,
+            resource_header: None,
+            near_limit_header: None,
+            alternate_names: &[],
+            marker_headers: &[],
+        }
+    }
+
+    /// Set the header for the number of seconds until reset
+    ///
+    /// Some vendors send it in addition to an absolute reset time.
+    /// It does not depend on the local clock, so it is preferred
+    /// by [`Headers::effective_reset`](super::Headers::effective_reset).
+    #[must_use]
+    pub(crate) const fn with_reset_after(self, reset_after_header: &'static str) -> Self {
+        Self {
+            reset_after_header: Some(reset_after_header),
+            ..self
         }
     }
+
+    /// Set the header for the duration of the rate limit interval in seconds
+    ///
+    /// Some vendors send the interval with each response instead of
+    /// documenting a fixed one. The header is required for the variant
+    /// to match, so it also tells apart vendors with otherwise equal headers.
+    #[must_use]
+    pub(crate) const fn with_window_header(self, window_header: &'static str) -> Self {
+        Self {
+            window_header: Some(window_header),
+            ..self
+        }
+    }
+
+    /// Set the header for the scope of the rate limit
+    ///
+    /// The header is optional, because it is usually only sent when
+    /// requests are throttled.
+    #[must_use]
+    pub(crate) const fn with_throttle_scope(self, throttle_scope_header: &'static str) -> Self {
+        Self {
+            throttle_scope_header: Some(throttle_scope_header),
+            ..self
+        }
+    }
+
+    /// Set the header for the resource which the rate limit applies to
+    ///
+    /// Vendors with several independent rate limits (e.g. Github's `core`
+    /// and `search`) send it to tell them apart. The header is optional.
+    #[must_use]
+    pub(crate) const fn with_resource_header(self, resource_header: &'static str) -> Self {
+        Self {
+            resource_header: Some(resource_header),
+            ..self
+        }
+    }
+
+    /// Set the header for whether the client is close to the limit
+    ///
+    /// Like the window header, it is required for the variant to match,
+    /// so it tells apart vendors with otherwise equal headers.
+    #[must_use]
+    pub(crate) const fn with_near_limit_header(self, near_limit_header: &'static str) -> Self {
+        Self {
+            near_limit_header: Some(near_limit_header),
+            ..self
+        }
+    }
+
+    /// Set alternative spellings of the header names
+    ///
+    /// Some vendors changed the case of their header names over time.
+    /// Each alternative name applies to the header whose name it matches
+    /// regardless of case, and is only tried if the header is not found
+    /// under its main name. This avoids duplicating the whole variant.
+    #[must_use]
+    pub(crate) const fn with_alternate_names(
+        self,
+        alternate_names: &'static [&'static str],
+    ) -> Self {
+        Self {
+            alternate_names,
+            ..self
+        }
+    }
+
+    /// Set headers of which at least one must be present for the variant
+    /// to match
+    ///
+    /// This tells apart vendors which use common header names, but always
+    /// send some vendor-specific header along with them.
+    #[must_use]
+    pub(crate) const fn with_marker_headers(self, marker_headers: &'static [&'static str]) -> Self {
+        Self {
+            marker_headers,
+            ..self
+        }
+    }
+
+    /// Look up one of this variant's headers in the given header map
+    ///
+    /// The main name is tried first, then the alternative spellings of it.
+    /// Returns the name under which the header was found and its value.
+    pub(crate) fn header<'a>(
+        &self,
+        header_map: &'a CaseSensitiveHeaderMap,
+        name: &'static str,
+    ) -> Option<(&'static str, &'a HeaderValue)> {
+        header_map.get(name).map(|value| (name, value)).or_else(|| {
+            self.alternate_names
+                .iter()
+                .filter(|alternate| alternate.eq_ignore_ascii_case(name))
+                .find_map(|&alternate| Some((alternate, header_map.get(alternate)?)))
+        })
+    }
+
+    /// Mark the remaining header as the percentage of the limit which was used
+    ///
+    /// The number of remaining requests is then derived from the limit.
+    #[must_use]
+    pub(crate) const fn with_remaining_percent_used(self) -> Self {
+        Self {
+            remaining_percent_used: true,
+            ..self
+        }
+    }
+
+    /// Check if all headers of this variant are present in the given header map
+    ///
+    /// This is the same as [`RateLimitVariant::present_headers`] returning
+    /// `Some`, but without allocating.
+    pub(crate) fn matches(&self, header_map: &CaseSensitiveHeaderMap) -> bool {
+        [
+            Some(self.remaining_header),
+            self.limit_header,
+            self.used_header,
+            self.window_header,
+            self.near_limit_header,
+        ]
+        .into_iter()
+        .flatten()
+        .all(|name| self.header(header_map, name).is_some())
+            && self.has_marker(header_map)
+            && self.reset_headers.iter().any(|&(name, kind)| {
+                self.header(header_map, name)
+                    .is_some_and(|(_, value)| ResetTime::new(value, kind).is_ok())
+            })
+    }
+
+    /// Get the names of this variant's headers found in the given header map
+    ///
+    /// Returns `None` unless all of the limit, used, remaining and window
+    /// headers of the variant and at least one of its reset headers are
+    /// present.
+    /// Reset headers only count if they can be parsed, because some vendors
+    /// share header names and only differ in the format of the reset time.
+    pub(crate) fn present_headers(
+        &self,
+        header_map: &CaseSensitiveHeaderMap,
+    ) -> Option<Vec<&'static str>> {
+        let mut names = Vec::new();
+        for name in [
+            Some(self.remaining_header),
+            self.limit_header,
+            self.used_header,
+            self.window_header,
+            self.near_limit_header,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            names.push(self.header(header_map, name)?.0);
+        }
+
+        if !self.has_marker(header_map) {
+            return None;
+        }
+        names.extend(
+            self.marker_headers
+                .iter()
+                .filter_map(|&name| Some(self.header(header_map, name)?.0)),
+        );
+
+        let resets = self.reset_headers.iter().filter_map(|&(name, kind)| {
+            self.header(header_map, name)
+                .filter(|(_, value)| ResetTime::new(value, kind).is_ok())
+                .map(|(name, _)| name)
+        });
+        let count = names.len();
+        names.extend(resets);
+        (names.len() > count).then_some(names)
+    }
+
+    /// Check if one of the marker headers is present, if there are any
+    fn has_marker(&self, header_map: &CaseSensitiveHeaderMap) -> bool {
+        self.marker_headers.is_empty()
+            || self
+                .marker_headers
+                .iter()
+                .any(|&name| self.header(header_map, name).is_some())
+    }
+
+    /// Vendor of the rate limit headers
+    #[must_use]
+    pub const fn vendor(&self) -> Vendor {
+        self.vendor
+    }
+
+    /// Duration of the rate limit interval, if known
+    #[must_use]
+    pub const fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Header name for the maximum number of requests
+    #[must_use]
+    pub const fn limit_header(&self) -> Option<&'static str> {
+        self.limit_header
+    }
+
+    /// Header name for the number of used requests
+    #[must_use]
+    pub const fn used_header(&self) -> Option<&'static str> {
+        self.used_header
+    }
+
+    /// Header name for the number of remaining requests
+    #[must_use]
+    pub const fn remaining_header(&self) -> &'static str {
+        self.remaining_header
+    }
+
+    /// Header names for the reset time and their kind of reset time
+    ///
+    /// A vendor can send the reset time in multiple headers with
+    /// different formats.
+    #[must_use]
+    pub const fn reset_headers(&self) -> &'static [(&'static str, ResetTimeKind)] {
+        self.reset_headers
+    }
+
+    /// Header name for the number of seconds until reset, if any
+    #[must_use]
+    pub const fn reset_after_header(&self) -> Option<&'static str> {
+        self.reset_after_header
+    }
+
+    /// Header name for the duration of the rate limit interval, if any
+    #[must_use]
+    pub const fn window_header(&self) -> Option<&'static str> {
+        self.window_header
+    }
+
+    /// Header name for the scope of the rate limit, if any
+    #[must_use]
+    pub const fn throttle_scope_header(&self) -> Option<&'static str> {
+        self.throttle_scope_header
+    }
+
+    /// Header name for the resource which the rate limit applies to, if any
+    #[must_use]
+    pub const fn resource_header(&self) -> Option<&'static str> {
+        self.resource_header
+    }
+
+    /// Alternative spellings of the header names
+    #[must_use]
+    pub const fn alternate_names(&self) -> &'static [&'static str] {
+        self.alternate_names
+    }
+
+    /// Header names of which at least one must be present for the variant
+    /// to match
+    #[must_use]
+    pub const fn marker_headers(&self) -> &'static [&'static str] {
+        self.marker_headers
+    }
+
+    /// Header name for whether the client is close to the limit, if any
+    #[must_use]
+    pub const fn near_limit_header(&self) -> Option<&'static str> {
+        self.near_limit_header
+    }
+
+    /// Whether the remaining header holds the percentage of the limit
+    /// which was used, rather than the number of remaining requests
+    #[must_use]
+    pub const fn remaining_percent_used(&self) -> bool {
+        self.remaining_percent_used
+    }
 }
 
 /// A rate limit header
@@ -73,24 +525,52 @@ impl RateLimitVariant {
 pub(crate) struct Limit {
     /// Maximum number of requests for the given interval
     pub(crate) count: usize,
+    /// The time window of the quota, if given inline (e.g. `100;window=60`)
+    pub(crate) window: Option<Duration>,
 }
 
+/// Names of the time window parameter of a quota policy
+const WINDOW_PARAMETERS: [&str; 2] = ["window", "w"];
+
 impl Limit {
     /// Create a new limit header
     ///
+    /// The draft allows a window (in seconds) as a parameter after the quota,
+    /// e.g. `100, 100;window=60`. Like any other list, only the last element
+    /// is used (see [`convert::last_value`]), so this is a quota of 100 in a
+    /// window of 60 seconds.
+    ///
     /// # Errors
     ///
-    /// This function returns an error if the header value cannot be parsed
+    /// This function returns an error if the header value cannot be parsed.
+    /// Fractional values are rounded down.
     pub(crate) fn new<T: AsRef<str>>(value: T) -> Result<Self> {
+        let value = value.as_ref();
+        let Some((quota, parameters)) = convert::last_value(value).split_once(';') else {
+            return Ok(Self::from(convert::to_usize_floor(value)?));
+        };
+        let window = parameters
+            .split(';')
+            .find_map(|parameter| {
+                let (key, seconds) = parameter.split_once('=')?;
+                WINDOW_PARAMETERS.contains(&key.trim()).then_some(seconds)
+            })
+            .map(convert::to_usize)
+            .transpose()?
+            .map(|seconds| Duration::seconds(i64::try_from(seconds).unwrap_or(i64::MAX)));
         Ok(Self {
-            count: convert::to_usize(value.as_ref())?,
+            count: convert::to_usize_floor(quota)?,
+            window,
         })
     }
 }
 
 impl From<usize> for Limit {
     fn from(count: usize) -> Self {
-        Self { count }
+        Self {
+            count,
+            window: None,
+        }
     }
 }
 
@@ -117,14 +597,26 @@ pub(crate) struct Remaining {
 }
 
 impl Remaining {
+    /// Derive the remaining requests from the percentage of the limit
+    /// which was used
+    ///
+    /// Partially used requests count as used, so the result is rounded down.
+    pub(crate) fn from_percent_used(limit: usize, percent: f64) -> Self {
+        let used = (limit as f64 * percent / 100.0).ceil() as usize;
+        Self {
+            count: limit.saturating_sub(used),
+        }
+    }
+
     /// Create a new remaining header
     ///
     /// # Errors
     ///
-    /// This function returns an error if the header value cannot be parsed
+    /// This function returns an error if the header value cannot be parsed.
+    /// Fractional values are rounded down.
     pub(crate) fn new(value: &str) -> Result<Self> {
         Ok(Self {
-            count: convert::to_usize(value)?,
+            count: convert::to_usize_floor(value)?,
         })
     }
 }
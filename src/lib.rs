@@ -11,25 +11,52 @@
     variant_size_differences,
     clippy::missing_const_for_fn
 )]
-#![deny(anonymous_parameters, macro_use_extern_crate, pointer_structural_match)]
+#![deny(anonymous_parameters, macro_use_extern_crate)]
 #![deny(missing_docs)]
 #![allow(clippy::module_name_repetitions)]
 
+mod builder;
 mod casesensitive_headermap;
 mod convert;
 mod error;
+mod ext;
+#[cfg(feature = "axum")]
+mod extract;
+mod named_limit;
 mod reset_time;
 
 pub mod headers;
 pub mod retryafter;
 
+use std::cmp::Ordering;
 use std::str::FromStr;
 
-use casesensitive_headermap::CaseSensitiveHeaderMap;
-use error::{Error, Result};
+use ::headers::HeaderValue;
+use error::Result;
+use time::OffsetDateTime;
 
-pub use headers::{Headers, Vendor};
-pub use reset_time::ResetTime;
+pub use builder::RateLimitBuilder;
+pub use casesensitive_headermap::CaseSensitiveHeaderMap;
+pub use error::Error;
+pub use ext::RateLimitExt;
+pub use headers::{Headers, RateLimitHeader, Vendor};
+pub use named_limit::NamedLimit;
+pub use reset_time::{ResetTime, ResetTimeKind};
+
+/// Accepted spellings of the HTTP `Date` header
+const DATE_HEADERS: [&str; 2] = ["Date", "date"];
+
+/// The kind of rate limit that was hit
+///
+/// Some vendors, most notably Github, have a primary quota and an additional
+/// short-term (secondary) limit to prevent abuse.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LimitKind {
+    /// A quota with a limit, remaining requests and a reset time
+    Primary,
+    /// A short-term limit which is only signaled by a `Retry-After` header
+    Secondary,
+}
 
 /// Rate Limit information, parsed from HTTP headers.
 ///
@@ -42,7 +69,8 @@ pub use reset_time::ResetTime;
 /// [ietf]: https://datatracker.ietf.org/doc/html/draft-polli-ratelimit-headers-00
 /// [retryafter]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After
 ///
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// Use [`RateLimit::cmp_by_reset`] to order rate limits by their reset time.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RateLimit {
     /// Rate limit information as per the [IETF "Polly" draft][ietf].
     Rfc6585(headers::Headers),
@@ -52,22 +80,325 @@ pub enum RateLimit {
 
 impl RateLimit {
     /// Create a new `RateLimit` from a `http::HeaderMap`.
+    ///
+    /// If both rate limit headers and a `Retry-After` header are present,
+    /// the one with the later reset time is used. The exception is an
+    /// exhausted quota (`remaining` is zero): in that case the `Retry-After`
+    /// header is the authoritative signal. This is how Github reports its
+    /// secondary rate limits, where the primary reset can be far in the
+    /// future.
     pub fn new<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
+        Self::from_header_map(&headers.into())
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`, or `None` if the
+    /// headers don't contain any rate limit information.
+    ///
+    /// Unlike [`RateLimit::new`], this tells apart responses without rate
+    /// limits (which is normal) from invalid rate limit headers.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if some rate limit headers are present,
+    /// but they are incomplete or cannot be parsed.
+    pub fn try_new<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+    ) -> std::result::Result<Option<Self>, Error> {
+        let headers = headers.into();
+        match Self::from_header_map(&headers) {
+            Ok(rate_limit) => Ok(Some(rate_limit)),
+            Err(e) if e.is_missing() && !headers::Headers::contains_any(&headers) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Create a new `RateLimit` from the status code and headers of a
+    /// response, or `None` if the response is not rate limited.
+    ///
+    /// Rate limiting is only considered active for `429 Too Many Requests`
+    /// and `503 Service Unavailable`. Other responses yield `None`, even if
+    /// they contain rate limit headers. This avoids false positives from
+    /// unrelated uses of `Retry-After`, e.g. on redirects.
+    /// The status code is kept in [`retryafter::RateLimit::status`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the response is rate limited, but
+    /// the rate limit headers are incomplete or cannot be parsed.
+    pub fn from_response_parts<T: Into<CaseSensitiveHeaderMap>>(
+        status: http::StatusCode,
+        headers: T,
+    ) -> std::result::Result<Option<Self>, Error> {
+        match status {
+            http::StatusCode::TOO_MANY_REQUESTS | http::StatusCode::SERVICE_UNAVAILABLE => {
+                let rate_limit = Self::try_new(headers)?.map(|rate_limit| match rate_limit {
+                    Self::RetryAfter(retryafter) => Self::RetryAfter(retryafter::RateLimit {
+                        status: Some(status),
+                        ..retryafter
+                    }),
+                    rate_limit @ Self::Rfc6585(_) => rate_limit,
+                });
+                Ok(rate_limit)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Create a new `RateLimit` from a borrowed header map.
+    ///
+    /// See [`RateLimit::new`] for details.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn from_header_map(headers: &CaseSensitiveHeaderMap) -> std::result::Result<Self, Error> {
+        Self::choose(
+            headers::Headers::from_header_map(headers),
+            retryafter::RateLimit::from_header_map(headers),
+        )
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`, only considering
+    /// the headers of the given vendor.
+    ///
+    /// Some vendors send the same header names, but in a different format.
+    /// If the vendor is known from the context (e.g. the host), this makes
+    /// sure that the headers are interpreted correctly.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the headers of the vendor are missing
+    /// or cannot be parsed, and there is no valid `Retry-After` header.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(%vendor)))]
+    pub fn new_with_vendor<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+        vendor: Vendor,
+    ) -> std::result::Result<Self, Error> {
         let headers = headers.into();
-        let rfc6585 = headers::Headers::new(headers.clone());
-        let retryafter = retryafter::RateLimit::new(headers);
+        Self::choose(
+            headers::Headers::from_header_map_with_vendor(&headers, vendor),
+            retryafter::RateLimit::from_header_map(&headers).map(|retryafter| {
+                retryafter::RateLimit {
+                    vendor: Some(vendor),
+                    ..retryafter
+                }
+            }),
+        )
+    }
 
+    /// Choose between rate limit headers and a `Retry-After` header.
+    ///
+    /// See [`RateLimit::new`] for details.
+    fn choose(
+        rfc6585: Result<headers::Headers>,
+        retryafter: Result<retryafter::RateLimit>,
+    ) -> std::result::Result<Self, Error> {
         match (rfc6585, retryafter) {
             (Ok(rfc6585), Ok(retryafter)) => {
-                if rfc6585.reset > retryafter.reset {
+                // `ResetTime` compares by point in time, so a relative reset
+                // (seconds) can be compared with an absolute one (date).
+                if rfc6585.remaining > 0
+                    && rfc6585
+                        .reset
+                        .cmp_at(&retryafter.reset, OffsetDateTime::now_utc())
+                        .is_gt()
+                {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        rfc6585.reset = ?rfc6585.reset,
+                        retryafter.reset = ?retryafter.reset,
+                        "using rate limit headers, which reset after Retry-After"
+                    );
                     Ok(Self::Rfc6585(rfc6585))
                 } else {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        rfc6585.remaining,
+                        rfc6585.reset = ?rfc6585.reset,
+                        retryafter.reset = ?retryafter.reset,
+                        "using Retry-After, because the quota is exhausted or resets earlier"
+                    );
                     Ok(Self::RetryAfter(retryafter))
                 }
             }
             (Ok(rfc6585), Err(_)) => Ok(Self::Rfc6585(rfc6585)),
             (Err(_), Ok(retryafter)) => Ok(Self::RetryAfter(retryafter)),
-            (Err(e), Err(_)) => Err(e),
+            (Err(e), Err(retryafter)) => {
+                // Report an invalid `Retry-After` header rather than the
+                // missing rate limit headers, so that it doesn't go unnoticed
+                let e = if e.is_missing() && !retryafter.is_missing() {
+                    retryafter
+                } else {
+                    e
+                };
+                #[cfg(feature = "tracing")]
+                tracing::debug!(error = %e, "no rate limit found");
+                Err(e)
+            }
+        }
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`, using the given
+    /// window if the vendor doesn't specify one.
+    ///
+    /// The window is unknown for [`Vendor::Standard`] and some other vendors,
+    /// which makes it impossible to reason about the request rate. If the
+    /// API's window is known from the context (e.g. its documentation), this
+    /// fills in [`Headers::window`]. A window sent by the vendor or documented
+    /// for it takes precedence. A `Retry-After` header has no window.
+    ///
+    /// # Errors
+    ///
+    /// This function returns the same errors as [`RateLimit::new`].
+    pub fn new_with_default_window<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+        window: time::Duration,
+    ) -> std::result::Result<Self, Error> {
+        Ok(match Self::new(headers)? {
+            Self::Rfc6585(rfc6585) => Self::Rfc6585(headers::Headers {
+                window: rfc6585.window.or(Some(window)),
+                ..rfc6585
+            }),
+            retryafter @ Self::RetryAfter(_) => retryafter,
+        })
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`, making sure that
+    /// the headers are not ambiguous.
+    ///
+    /// [`RateLimit::new`] uses the first matching vendor if the headers
+    /// of more than one vendor are present. This function returns an
+    /// error instead, which helps to detect when a vendor hint is needed.
+    ///
+    /// # Errors
+    ///
+    /// This function returns [`Error::AmbiguousVendor`] if the headers of
+    /// more than one vendor are all present, or any error of
+    /// [`RateLimit::new`].
+    pub fn new_checked<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+    ) -> std::result::Result<Self, Error> {
+        let headers = headers.into();
+        headers::Headers::check_ambiguity(&headers)?;
+        Self::from_header_map(&headers)
+    }
+
+    /// Create a new `RateLimit` from a `http::HeaderMap`, falling back to
+    /// case-insensitive matching of header names.
+    ///
+    /// Vendors are usually told apart by the case of their header names.
+    /// HTTP/2 and HTTP/3 require lowercase header names, though, and
+    /// `http::HeaderMap` always lowercases them. So e.g. Vimeo's
+    /// `X-RateLimit-Limit` arrives as `x-ratelimit-limit`, which is Github's
+    /// spelling. Use this function if the headers may have been lowercased.
+    ///
+    /// The headers are parsed in this order, until one succeeds:
+    ///
+    /// 1. The first vendor whose headers are all present with the exact case
+    /// 2. The first vendor whose headers are all present regardless of case
+    /// 3. Incomplete headers, like with [`RateLimit::new`], first with the
+    ///    exact case and then regardless of case
+    ///
+    /// If several vendors share the same header names apart from their case,
+    /// the first one in the order of [`headers::known_variants`] whose reset
+    /// time can be parsed wins. So the vendor may be wrong, but the values
+    /// are read consistently from one vendor's headers.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the rate limit cannot be parsed
+    /// from the headers, even when ignoring the case of header names.
+    pub fn new_case_insensitive<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+    ) -> std::result::Result<Self, Error> {
+        let headers = headers.into();
+        let canonical = Self::canonicalize(&headers);
+        let rfc6585 = headers::Headers::from_complete_variant(&headers)
+            .or_else(|| headers::Headers::from_complete_variant(&canonical))
+            .unwrap_or_else(|| match headers::Headers::from_header_map(&headers) {
+                Err(e) if e.is_missing() => headers::Headers::from_header_map(&canonical),
+                result => result,
+            });
+        Self::choose(rfc6585, retryafter::RateLimit::from_header_map(&canonical))
+    }
+
+    /// Add every header under all known spellings of its name
+    ///
+    /// Headers which are already present under a known spelling are kept.
+    fn canonicalize(headers: &CaseSensitiveHeaderMap) -> CaseSensitiveHeaderMap {
+        let mut canonical = headers.clone();
+        for (name, value) in headers {
+            for known in headers::Headers::known_header_names()
+                .chain(retryafter::RETRY_AFTER_HEADERS)
+                .filter(|known| known.eq_ignore_ascii_case(name))
+            {
+                if canonical.get(known).is_none() {
+                    canonical.insert(known.to_string(), value.clone());
+                }
+            }
+        }
+        canonical
+    }
+
+    /// Get the server's time from the HTTP `Date` header
+    ///
+    /// This can be used as the reference for absolute reset times with
+    /// [`ResetTime::seconds_with_reference`], so that a skewed local clock
+    /// does not matter. Returns `None` if there is no valid `Date` header.
+    pub fn server_time<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> Option<OffsetDateTime> {
+        let headers = headers.into();
+        let value = DATE_HEADERS
+            .iter()
+            .find_map(|name| headers.get_str(name))?
+            .ok()?;
+        match ResetTime::parse(value, ResetTimeKind::ImfFixdate) {
+            Ok(ResetTime::DateTime(date)) => Some(date),
+            _ => None,
+        }
+    }
+
+    /// Create a new `RateLimit` from an iterator of header name and value pairs.
+    ///
+    /// This is useful if the headers are not available as an
+    /// `http::HeaderMap`. Header names are matched case-sensitively,
+    /// just like with [`RateLimit::new`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a header value is invalid or if
+    /// the rate limit cannot be parsed from the headers.
+    pub fn from_pairs<I>(pairs: I) -> std::result::Result<Self, Error>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut headers = CaseSensitiveHeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name, HeaderValue::from_str(value.trim())?);
+        }
+        RateLimit::new(headers)
+    }
+
+    /// Create a new `RateLimit` from raw header lines, e.g.
+    /// `b"Retry-After: 30\r\n"`.
+    ///
+    /// Like with [`RateLimit::from_str`], lines without a colon and headers
+    /// with invalid values are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the bytes are not valid UTF-8 or
+    /// if the rate limit cannot be parsed from the headers.
+    pub fn from_bytes(headers: &[u8]) -> std::result::Result<Self, Error> {
+        Self::from_str(std::str::from_utf8(headers)?)
+    }
+
+    /// Get the kind of rate limit.
+    ///
+    /// Rate limits with the full set of quota headers are considered
+    /// primary limits, whereas a bare `Retry-After` signals a secondary limit.
+    /// Clients should back off briefly for secondary limits and wait for the
+    /// quota window to reset for primary ones.
+    pub const fn kind(&self) -> LimitKind {
+        match self {
+            Self::Rfc6585(_) => LimitKind::Primary,
+            Self::RetryAfter(_) => LimitKind::Secondary,
         }
     }
 
@@ -80,12 +411,48 @@ impl RateLimit {
         }
     }
 
+    /// Get the vendor which sent the rate limit headers, if known
+    ///
+    /// For rate limit headers, the vendor is detected from the header names.
+    /// A bare `Retry-After` header does not reveal the vendor, so it is only
+    /// known if it was given with [`retryafter::RateLimit::new_with_vendor`].
+    #[must_use]
+    pub const fn vendor(&self) -> Option<Vendor> {
+        match self {
+            Self::Rfc6585(rfc6585) => Some(rfc6585.vendor),
+            Self::RetryAfter(retryafter) => retryafter.vendor,
+        }
+    }
+
+    /// Get the number of seconds until requests can be made again.
+    ///
+    /// This is zero if the reset time has already passed. If the server
+    /// sent a relative reset time in addition to an absolute one, the
+    /// relative one is used (see [`Headers::effective_reset`]).
+    #[must_use]
+    pub fn seconds_until_reset(&self) -> usize {
+        match self {
+            Self::Rfc6585(rfc6585) => rfc6585.effective_reset().seconds(),
+            Self::RetryAfter(retryafter) => retryafter.reset.seconds(),
+        }
+    }
+
+    /// Get the time until requests can be made again as a
+    /// `std::time::Duration`, e.g. for `std::thread::sleep`.
+    ///
+    /// Reset times in the past yield a zero duration.
+    /// See [`RateLimit::seconds_until_reset`] for details.
+    #[must_use]
+    pub fn std_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.seconds_until_reset() as u64)
+    }
+
     /// Get `limit` value.
     ///
     /// This is the maximum number of requests that can be made in a given time window.
     pub const fn limit(&self) -> Option<usize> {
         match self {
-            Self::Rfc6585(rfc6585) => Some(rfc6585.limit),
+            Self::Rfc6585(rfc6585) => rfc6585.limit,
             Self::RetryAfter(_) => None,
         }
     }
@@ -99,6 +466,124 @@ impl RateLimit {
             Self::RetryAfter(_) => None,
         }
     }
+
+    /// Get `remaining` value, or `default` if it is unknown.
+    ///
+    /// A bare `Retry-After` header does not tell the number of remaining
+    /// requests. Pass zero to pessimistically assume that no requests are
+    /// left until the rate limit resets.
+    #[must_use]
+    pub const fn remaining_or(&self, default: usize) -> usize {
+        match self {
+            Self::Rfc6585(rfc6585) => rfc6585.remaining,
+            Self::RetryAfter(_) => default,
+        }
+    }
+
+    /// Get the limit, remaining requests and reset time as a tuple
+    ///
+    /// The limit and remaining requests are only available
+    /// for rate limit headers, not for `Retry-After`.
+    #[must_use]
+    pub const fn as_tuple(&self) -> (Option<usize>, Option<usize>, ResetTime) {
+        match self {
+            Self::Rfc6585(rfc6585) => (rfc6585.limit, Some(rfc6585.remaining), rfc6585.reset),
+            Self::RetryAfter(retryafter) => (None, None, retryafter.reset),
+        }
+    }
+
+    /// Get the names of the headers which were used to parse the rate limit
+    ///
+    /// This is useful for debugging and for removing the rate limit headers
+    /// before forwarding a response.
+    #[must_use]
+    pub fn matched_headers(&self) -> Vec<&str> {
+        match self {
+            Self::Rfc6585(rfc6585) => rfc6585.matched_headers.iter().map(AsRef::as_ref).collect(),
+            Self::RetryAfter(retryafter) => vec![retryafter.header],
+        }
+    }
+
+    /// Get the rate limits of named buckets in the response
+    ///
+    /// Vendors with several independent rate limits send the name of the
+    /// bucket which the request counted against (see [`NamedLimit`]).
+    /// There is one entry per parsed dimension, e.g. `requests` and `tokens`
+    /// for OpenAI. The result is empty if the vendor didn't send a name or
+    /// if only a `Retry-After` header was found.
+    #[must_use]
+    pub fn named_limits(&self) -> Vec<NamedLimit> {
+        match self {
+            Self::Rfc6585(rfc6585) => NamedLimit::from_headers(rfc6585)
+                .into_iter()
+                .chain(rfc6585.other_limits.iter().cloned())
+                .collect(),
+            Self::RetryAfter(_) => Vec::new(),
+        }
+    }
+
+    /// Account for the time which passed since the rate limit was parsed,
+    /// e.g. to keep a cached rate limit meaningful
+    ///
+    /// Relative reset times (including [`Headers::reset_after`]) are
+    /// decremented by `elapsed`, saturating at zero. Absolute reset times
+    /// don't change. See [`ResetTime::age_by`].
+    pub fn age_by(&mut self, elapsed: time::Duration) {
+        let elapsed = elapsed.max(time::Duration::ZERO);
+        match self {
+            Self::Rfc6585(rfc6585) => {
+                rfc6585.reset.age_by(elapsed);
+                rfc6585.reset_after = rfc6585.reset_after.map(|reset_after| {
+                    reset_after
+                        .saturating_sub(elapsed)
+                        .max(time::Duration::ZERO)
+                });
+                for other in &mut rfc6585.other_limits {
+                    other.reset.age_by(elapsed);
+                }
+            }
+            Self::RetryAfter(retryafter) => retryafter.reset.age_by(elapsed),
+        }
+    }
+
+    /// Compare two rate limits by their reset time, so that the one which
+    /// gets lifted first compares as the smallest
+    ///
+    /// This is not an [`Ord`] implementation, because rate limits with the
+    /// same reset time aren't equal. Use it with e.g.
+    /// `limits.sort_by(RateLimit::cmp_by_reset)`.
+    ///
+    /// Relative reset times are compared at the current time. To compare
+    /// many rate limits at one fixed time, use [`ResetTime::cmp_at`].
+    #[must_use]
+    pub fn cmp_by_reset(&self, other: &Self) -> Ordering {
+        self.reset()
+            .cmp_at(&other.reset(), OffsetDateTime::now_utc())
+    }
+
+    /// Combine two rate limits into a conservative estimate.
+    ///
+    /// This is useful when making concurrent requests to the same API.
+    /// The result keeps the smaller number of remaining requests and the later
+    /// reset time. A bare `Retry-After` means that no requests are allowed
+    /// until it resets, so it is more restrictive than any quota.
+    #[must_use]
+    pub fn most_restrictive(self, other: Self) -> Self {
+        let now = OffsetDateTime::now_utc();
+        let reset = std::cmp::max_by(self.reset(), other.reset(), |a, b| a.cmp_at(b, now));
+        match (self, other) {
+            (Self::Rfc6585(a), Self::Rfc6585(b)) => {
+                let headers = if b.remaining < a.remaining { b } else { a };
+                Self::Rfc6585(headers::Headers { reset, ..headers })
+            }
+            (Self::RetryAfter(retryafter), _) | (_, Self::RetryAfter(retryafter)) => {
+                Self::RetryAfter(retryafter::RateLimit {
+                    reset,
+                    ..retryafter
+                })
+            }
+        }
+    }
 }
 
 impl FromStr for RateLimit {
@@ -109,6 +594,18 @@ impl FromStr for RateLimit {
     }
 }
 
+impl From<headers::Headers> for RateLimit {
+    fn from(headers: headers::Headers) -> Self {
+        Self::Rfc6585(headers)
+    }
+}
+
+impl From<retryafter::RateLimit> for RateLimit {
+    fn from(retryafter: retryafter::RateLimit) -> Self {
+        Self::RetryAfter(retryafter)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,13 +621,13 @@ mod tests {
             X-Ratelimit-Used: 100
             X-Ratelimit-Remaining: 22
             X-Ratelimit-Reset: 30
-            Retry-After: Wed, 21 Oct 2015 07:28:00 GMT
+            Retry-After: Fri, 01 Jan 2100 07:28:00 GMT
         "};
 
         let rate = RateLimit::from_str(headers).unwrap();
         assert_eq!(
             rate.reset(),
-            ResetTime::DateTime(datetime!(2015-10-21 7:28:00.0 UTC))
+            ResetTime::DateTime(datetime!(2100-01-01 7:28:00.0 UTC))
         );
     }
 
@@ -146,4 +643,780 @@ mod tests {
         let rate = RateLimit::from_str(headers).unwrap();
         assert_eq!(rate.reset(), ResetTime::Seconds(30));
     }
+
+    #[test]
+    fn compare_timestamp_reset_with_retry_after_seconds() {
+        // The timestamp lies in the past, so `Retry-After` is later
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+            retry-after: 60
+        "};
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(60));
+
+        // The timestamp lies far in the future, so it is later
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 4102444800
+            retry-after: 60
+        "};
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2100-01-01 0:00:00 UTC))
+        );
+    }
+
+    #[test]
+    fn compare_seconds_reset_with_past_retry_after_date() {
+        let headers = indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+            Retry-After: Wed, 21 Oct 2015 07:28:00 GMT
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+    }
+
+    #[test]
+    fn prefer_retry_after_when_exhausted() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 0
+            x-ratelimit-reset: 4102444800
+            retry-after: 60
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(60));
+        assert_eq!(rate.kind(), LimitKind::Secondary);
+    }
+
+    #[test]
+    fn order_by_reset_time() {
+        let soon = RateLimit::from_str("Retry-After: 30").unwrap();
+        let later = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 4102444800
+        "})
+        .unwrap();
+        let past = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+
+        assert_eq!(soon.cmp_by_reset(&later), Ordering::Less);
+        assert_eq!(past.cmp_by_reset(&soon), Ordering::Less);
+        assert_eq!(later.cmp_by_reset(&later), Ordering::Equal);
+
+        let mut limits = vec![later.clone(), soon.clone(), past.clone()];
+        limits.sort_by(RateLimit::cmp_by_reset);
+        assert_eq!(limits, vec![past, soon, later]);
+    }
+
+    #[test]
+    fn parse_without_limit() {
+        let headers = indoc! {"
+            x-ratelimit-remaining: 10
+            x-ratelimit-reset: 1350085394
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.limit(), None);
+        assert_eq!(rate.remaining(), Some(10));
+    }
+
+    #[test]
+    fn rate_limit_as_tuple() {
+        let rate = RateLimit::from_str("Retry-After: 10").unwrap();
+        assert_eq!(rate.as_tuple(), (None, None, ResetTime::Seconds(10)));
+
+        let rate = RateLimit::from_str(indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+        "})
+        .unwrap();
+        assert_eq!(
+            rate.as_tuple(),
+            (Some(122), Some(22), ResetTime::Seconds(30))
+        );
+    }
+
+    #[test]
+    fn merge_most_restrictive() {
+        let a = RateLimit::from_str(indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+        "})
+        .unwrap();
+        let b = RateLimit::from_str(indoc! {"
+            X-Ratelimit-Used: 110
+            X-Ratelimit-Remaining: 12
+            X-Ratelimit-Reset: 20
+        "})
+        .unwrap();
+
+        let merged = a.clone().most_restrictive(b.clone());
+        assert_eq!(merged.remaining(), Some(12));
+        assert_eq!(merged.reset(), ResetTime::Seconds(30));
+        assert_eq!(merged, b.most_restrictive(a.clone()));
+
+        let retry = RateLimit::from_str("Retry-After: 10").unwrap();
+        let merged = a.most_restrictive(retry);
+        assert_eq!(merged.kind(), LimitKind::Secondary);
+        assert_eq!(merged.reset(), ResetTime::Seconds(30));
+    }
+
+    #[test]
+    fn reset_time_from_duration() {
+        use time::Duration;
+
+        assert_eq!(
+            ResetTime::from_duration(Duration::seconds(42)),
+            ResetTime::Seconds(42)
+        );
+        assert_eq!(
+            ResetTime::from_duration(Duration::milliseconds(1500)),
+            ResetTime::Seconds(1)
+        );
+        assert_eq!(
+            ResetTime::from_duration(Duration::seconds(-5)),
+            ResetTime::ZERO
+        );
+    }
+
+    #[test]
+    fn reset_time_seconds_saturate() {
+        use time::macros::datetime;
+
+        let past = ResetTime::DateTime(datetime!(2000-01-01 00:00:00 UTC));
+        assert_eq!(past.seconds(), 0);
+        assert_eq!(past.try_seconds(), Some(0));
+
+        // A timestamp in milliseconds, misparsed as seconds
+        let distant = ResetTime::DateTime(datetime!(9999-12-31 23:59:59 UTC));
+        assert_eq!(distant.seconds(), ResetTime::MAX_SECONDS);
+        assert_eq!(distant.try_seconds(), None);
+
+        assert_eq!(ResetTime::Seconds(30).try_seconds(), Some(30));
+        assert_eq!(ResetTime::Seconds(usize::MAX).try_seconds(), None);
+    }
+
+    #[test]
+    fn reset_time_seconds_with_reference() {
+        use time::macros::datetime;
+
+        let server_time = datetime!(2012-10-12 23:40:00 UTC);
+        let reset = ResetTime::DateTime(datetime!(2012-10-12 23:43:14 UTC));
+        assert_eq!(reset.seconds_with_reference(server_time), 194);
+        assert_eq!(
+            reset.seconds_with_reference(datetime!(2012-10-13 00:00:00 UTC)),
+            0
+        );
+        assert_eq!(
+            ResetTime::Seconds(30).seconds_with_reference(server_time),
+            30
+        );
+    }
+
+    #[test]
+    fn read_server_time() {
+        use time::macros::datetime;
+
+        let headers = indoc! {"
+            Date: Fri, 12 Oct 2012 23:40:00 GMT
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+        let server_time = RateLimit::server_time(headers).unwrap();
+        assert_eq!(server_time, datetime!(2012-10-12 23:40:00 UTC));
+
+        let rate = RateLimit::new(headers).unwrap();
+        assert_eq!(rate.reset().seconds_with_reference(server_time), 194);
+
+        assert_eq!(RateLimit::server_time("date: yesterday"), None);
+        assert_eq!(RateLimit::server_time("Retry-After: 10"), None);
+    }
+
+    #[test]
+    fn rate_limit_vendor() {
+        let rate = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Github));
+
+        let rate = RateLimit::from_str("Retry-After: 10").unwrap();
+        assert_eq!(rate.vendor(), None);
+
+        let rate = RateLimit::RetryAfter(
+            retryafter::RateLimit::new_with_vendor("Retry-After: 10", Vendor::Notion).unwrap(),
+        );
+        assert_eq!(rate.vendor(), Some(Vendor::Notion));
+    }
+
+    #[test]
+    fn seconds_until_reset() {
+        let rate = RateLimit::from_str("Retry-After: 10").unwrap();
+        assert_eq!(rate.seconds_until_reset(), 10);
+
+        let rate = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(rate.seconds_until_reset(), 0);
+
+        let rate = RateLimit::from_str(indoc! {"
+            X-RateLimit-Limit: 5
+            X-RateLimit-Remaining: 0
+            X-RateLimit-Reset: 1470173023.123
+            X-RateLimit-Reset-After: 1.2
+        "})
+        .unwrap();
+        assert_eq!(rate.seconds_until_reset(), 2);
+    }
+
+    #[test]
+    fn matched_headers() {
+        let rate = RateLimit::from_str(indoc! {"
+            X-RateLimit-Limit: 5
+            X-RateLimit-Remaining: 0
+            X-RateLimit-Reset: 1470173023.123
+            X-RateLimit-Reset-After: 1.2
+            Content-Type: application/json
+        "})
+        .unwrap();
+        assert_eq!(
+            rate.matched_headers(),
+            vec![
+                "X-RateLimit-Remaining",
+                "X-RateLimit-Limit",
+                "X-RateLimit-Reset",
+                "X-RateLimit-Reset-After"
+            ]
+        );
+
+        let rate = RateLimit::from_str("retry-after: 10").unwrap();
+        assert_eq!(rate.matched_headers(), vec!["retry-after"]);
+
+        let rate = RateLimit::from_str("RateLimit: limit=100, remaining=42, reset=30").unwrap();
+        assert_eq!(rate.matched_headers(), vec!["RateLimit"]);
+    }
+
+    #[test]
+    fn named_limits() {
+        let rate = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 30
+            x-ratelimit-remaining: 29
+            x-ratelimit-reset: 1350085394
+            x-ratelimit-resource: search
+        "})
+        .unwrap();
+        assert_eq!(
+            rate.named_limits(),
+            vec![NamedLimit {
+                name: "search".to_string(),
+                limit: Some(30),
+                remaining: 29,
+                reset: rate.reset(),
+            }]
+        );
+
+        let rate = RateLimit::from_str(indoc! {"
+            RateLimit-Limit: 1000
+            RateLimit-Remaining: 0
+            RateLimit-Reset: 30
+            x-ms-throttle-scope: Tenant_Application/ReadWrite/9a3f5c6e/1b2c3d4e
+        "})
+        .unwrap();
+        assert_eq!(
+            rate.named_limits()[0].name,
+            "Tenant_Application/ReadWrite/9a3f5c6e/1b2c3d4e"
+        );
+
+        let rate = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit-requests: 60
+            x-ratelimit-limit-tokens: 150000
+            x-ratelimit-remaining-requests: 59
+            x-ratelimit-remaining-tokens: 149984
+            x-ratelimit-reset-requests: 6m0s
+            x-ratelimit-reset-tokens: 1.5s
+        "})
+        .unwrap();
+        assert_eq!(
+            rate.named_limits(),
+            vec![
+                NamedLimit {
+                    name: "requests".to_string(),
+                    limit: Some(60),
+                    remaining: 59,
+                    reset: ResetTime::Seconds(360),
+                },
+                NamedLimit {
+                    name: "tokens".to_string(),
+                    limit: Some(150_000),
+                    remaining: 149_984,
+                    reset: ResetTime::Seconds(2),
+                },
+            ]
+        );
+
+        let rate = RateLimit::from_str("retry-after: 10").unwrap();
+        assert!(rate.named_limits().is_empty());
+    }
+
+    #[test]
+    fn remaining_or_default() {
+        let rate = RateLimit::from_str("Retry-After: 10").unwrap();
+        assert_eq!(rate.remaining_or(0), 0);
+
+        let rate = RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(rate.remaining_or(0), 4987);
+    }
+
+    #[test]
+    fn convert_from_parts() {
+        let headers = Headers::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(
+            RateLimit::from(headers.clone()),
+            RateLimit::Rfc6585(headers)
+        );
+
+        let retryafter = retryafter::RateLimit::from_str("Retry-After: 10").unwrap();
+        assert_eq!(
+            RateLimit::from(retryafter),
+            RateLimit::RetryAfter(retryafter)
+        );
+    }
+
+    #[test]
+    fn std_duration() {
+        use std::time::Duration;
+
+        let rate = RateLimit::from_str("Retry-After: 10").unwrap();
+        assert_eq!(rate.std_duration(), Duration::from_secs(10));
+
+        let rate = RateLimit::from_str("Retry-After: Fri, 31 Dec 1999 23:59:59 GMT").unwrap();
+        assert_eq!(rate.std_duration(), Duration::ZERO);
+    }
+
+    #[test]
+    fn reset_time_has_passed() {
+        use time::macros::datetime;
+
+        assert!(ResetTime::ZERO.has_passed());
+        assert!(!ResetTime::ZERO.is_future());
+        assert!(ResetTime::Seconds(1).is_future());
+
+        let past = ResetTime::DateTime(datetime!(2000-01-01 00:00:00 UTC));
+        assert!(past.has_passed());
+        let future = ResetTime::DateTime(datetime!(2100-01-01 00:00:00 UTC));
+        assert!(future.is_future());
+    }
+
+    #[test]
+    fn reset_time_default() {
+        assert_eq!(ResetTime::ZERO, ResetTime::Seconds(0));
+        assert_eq!(ResetTime::default(), ResetTime::ZERO);
+    }
+
+    #[test]
+    fn reset_time_as_unix_timestamp() {
+        let reset = ResetTime::DateTime(datetime!(2012-10-12 23:43:14 UTC));
+        assert_eq!(reset.as_unix_timestamp(), 1_350_085_394);
+
+        let before = OffsetDateTime::now_utc().unix_timestamp();
+        let timestamp = ResetTime::Seconds(60).as_unix_timestamp();
+        let after = OffsetDateTime::now_utc().unix_timestamp();
+        assert!((before + 60..=after + 60).contains(&timestamp));
+    }
+
+    #[test]
+    fn new_checked_ambiguous() {
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: Tue, 15 Nov 1994 08:12:31 GMT
+            X-RateLimit-Next: 2023-01-01T00:00:00Z
+        "})
+        .unwrap();
+        assert!(matches!(
+            RateLimit::new_checked(headers),
+            Err(Error::AmbiguousVendor { .. })
+        ));
+
+        // Algolia sends the same headers as Github, so it needs a vendor hint
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(
+            RateLimit::new_checked(headers).unwrap().vendor(),
+            Some(Vendor::Github)
+        );
+
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            Fastly-RateLimit-Limit: 100
+            Fastly-RateLimit-Remaining: 99
+            Fastly-RateLimit-Reset: 30
+        "})
+        .unwrap();
+        assert!(RateLimit::new_checked(headers).is_ok());
+    }
+
+    #[test]
+    fn new_with_default_window() {
+        use time::Duration;
+
+        let headers = indoc! {"
+            RateLimit-Limit: 100
+            Ratelimit-Remaining: 50
+            Ratelimit-Reset: 20
+        "};
+        let rate = RateLimit::new_with_default_window(headers, Duration::MINUTE).unwrap();
+        let RateLimit::Rfc6585(rfc6585) = rate else {
+            panic!("expected rate limit headers");
+        };
+        assert_eq!(rfc6585.window, Some(Duration::MINUTE));
+
+        // The vendor's window takes precedence
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+        let rate = RateLimit::new_with_default_window(headers, Duration::MINUTE).unwrap();
+        let RateLimit::Rfc6585(rfc6585) = rate else {
+            panic!("expected rate limit headers");
+        };
+        assert_eq!(rfc6585.window, Some(Duration::HOUR));
+
+        let rate = RateLimit::new_with_default_window("Retry-After: 10", Duration::MINUTE).unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(10));
+    }
+
+    #[test]
+    fn age_by() {
+        use time::Duration;
+
+        let mut rate = RateLimit::from_str("Retry-After: 30").unwrap();
+        rate.age_by(Duration::seconds(10));
+        assert_eq!(rate.reset(), ResetTime::Seconds(20));
+        rate.age_by(Duration::minutes(1));
+        assert_eq!(rate.reset(), ResetTime::Seconds(0));
+
+        // Absolute reset times don't change, relative ones do
+        let headers = indoc! {"
+            X-RateLimit-Limit: 5
+            X-RateLimit-Remaining: 0
+            X-RateLimit-Reset: 1470173023
+            X-RateLimit-Reset-After: 30
+            X-RateLimit-Bucket: abcd1234
+        "};
+        let mut rate = RateLimit::new(headers).unwrap();
+        rate.age_by(Duration::seconds(10));
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2016-08-02 21:23:43 UTC))
+        );
+        assert_eq!(rate.seconds_until_reset(), 20);
+
+        // Negative durations don't increase the reset time
+        rate.age_by(Duration::seconds(-10));
+        assert_eq!(rate.seconds_until_reset(), 20);
+        rate.age_by(Duration::MIN);
+        assert_eq!(rate.seconds_until_reset(), 20);
+        rate.age_by(Duration::MAX);
+        assert_eq!(rate.seconds_until_reset(), 0);
+    }
+
+    #[test]
+    fn new_case_insensitive() {
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            X-RATELIMIT-LIMIT: 5000
+            X-RATELIMIT-REMAINING: 4987
+            X-RATELIMIT-RESET: 1350085394
+        "})
+        .unwrap();
+        assert_eq!(
+            RateLimit::new(headers.clone()).unwrap().vendor(),
+            Some(Vendor::Standard)
+        );
+        let rate = RateLimit::new_case_insensitive(headers).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Github));
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), Some(4987));
+
+        let headers = CaseSensitiveHeaderMap::from_str("RETRY-AFTER: 10").unwrap();
+        assert_eq!(
+            RateLimit::new_case_insensitive(headers).unwrap().reset(),
+            ResetTime::Seconds(10)
+        );
+
+        // Exact matches are preferred over the fallback
+        let headers = CaseSensitiveHeaderMap::from_str(indoc! {"
+            X-RateLimit-Limit: 10
+            X-RateLimit-Remaining: 9
+            X-RateLimit-Reset: 60
+            x-ratelimit-remaining: 1
+        "})
+        .unwrap();
+        assert_eq!(
+            RateLimit::new_case_insensitive(headers)
+                .unwrap()
+                .remaining(),
+            Some(9)
+        );
+
+        let headers = CaseSensitiveHeaderMap::from_str("Content-Type: text/html").unwrap();
+        assert!(RateLimit::new_case_insensitive(headers)
+            .unwrap_err()
+            .is_missing());
+    }
+
+    /// Build a `http::HeaderMap`, which lowercases header names like HTTP/2
+    fn lowercased(headers: &str) -> http::HeaderMap {
+        headers
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| {
+                (
+                    http::HeaderName::from_str(name).unwrap(),
+                    HeaderValue::from_str(value.trim()).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn new_case_insensitive_lowercased_headers() {
+        // Vimeo
+        let headers = lowercased(indoc! {"
+            X-RateLimit-Limit: 60
+            X-RateLimit-Remaining: 59
+            X-RateLimit-Reset: Tue, 15 Nov 1994 08:12:31 GMT
+        "});
+        assert!(RateLimit::new(&headers).is_err());
+        let rate = RateLimit::new_case_insensitive(&headers).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Vimeo));
+        assert_eq!(rate.remaining(), Some(59));
+
+        // Mastodon
+        let headers = lowercased(indoc! {"
+            X-RateLimit-Limit: 300
+            X-RateLimit-Remaining: 299
+            X-RateLimit-Reset: 2100-01-01T00:00:00.000000Z
+        "});
+        assert!(RateLimit::new(&headers).is_err());
+        let rate = RateLimit::new_case_insensitive(&headers).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Mastodon));
+        assert_eq!(
+            rate.reset(),
+            ResetTime::DateTime(datetime!(2100-01-01 0:00:00 UTC))
+        );
+
+        // Reddit sends no limit header, so it is not mistaken for Github
+        let headers = lowercased(indoc! {"
+            X-Ratelimit-Used: 100
+            X-Ratelimit-Remaining: 22
+            X-Ratelimit-Reset: 30
+        "});
+        let rate = RateLimit::new_case_insensitive(&headers).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Reddit));
+        assert_eq!(rate.limit(), Some(122));
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+
+        // Github and Standard headers are unaffected
+        let headers = lowercased(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "});
+        let rate = RateLimit::new_case_insensitive(&headers).unwrap();
+        assert_eq!(rate, RateLimit::new(&headers).unwrap());
+        assert_eq!(rate.vendor(), Some(Vendor::Github));
+
+        // Vendors which only differ in the case of their headers can't be
+        // told apart, so the first matching one in the variant order wins
+        let headers = lowercased(indoc! {"
+            X-RateLimit-Limit: 5
+            X-RateLimit-Remaining: 0
+            X-RateLimit-Reset: 1470173023
+        "});
+        let rate = RateLimit::new_case_insensitive(&headers).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Github));
+    }
+
+    #[test]
+    fn missing_headers_error() {
+        assert!(RateLimit::from_str("Content-Type: text/html")
+            .unwrap_err()
+            .is_missing());
+        assert!(!RateLimit::from_str("Retry-After: soon")
+            .unwrap_err()
+            .is_missing());
+        assert!(!RateLimit::from_str(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: lots
+            x-ratelimit-reset: 1350085394
+        "})
+        .unwrap_err()
+        .is_missing());
+    }
+
+    #[test]
+    fn try_new_without_rate_limit() {
+        assert_eq!(RateLimit::try_new("Content-Type: text/html").unwrap(), None);
+        assert_eq!(
+            RateLimit::try_new("Retry-After: 10").unwrap(),
+            RateLimit::new("Retry-After: 10").ok()
+        );
+
+        // Rate limit headers are present, but incomplete
+        let err = RateLimit::try_new(indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+        "})
+        .unwrap_err();
+        assert!(matches!(err, Error::MissingReset { .. }));
+
+        assert!(RateLimit::try_new("Retry-After: soon").is_err());
+    }
+
+    #[test]
+    fn from_response_parts_status() {
+        use http::StatusCode;
+
+        let rate = RateLimit::from_response_parts(StatusCode::TOO_MANY_REQUESTS, "Retry-After: 10")
+            .unwrap()
+            .unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(10));
+        assert!(matches!(rate, RateLimit::RetryAfter(r) if r.is_rate_limit()));
+        assert!(
+            RateLimit::from_response_parts(StatusCode::SERVICE_UNAVAILABLE, "Retry-After: 10")
+                .unwrap()
+                .is_some()
+        );
+
+        // Stray `Retry-After` headers on other responses are ignored
+        assert_eq!(
+            RateLimit::from_response_parts(StatusCode::MOVED_PERMANENTLY, "Retry-After: 10")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            RateLimit::from_response_parts(StatusCode::OK, "Retry-After: soon").unwrap(),
+            None
+        );
+
+        assert_eq!(
+            RateLimit::from_response_parts(StatusCode::TOO_MANY_REQUESTS, "").unwrap(),
+            None
+        );
+        assert!(
+            RateLimit::from_response_parts(StatusCode::TOO_MANY_REQUESTS, "Retry-After: soon")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_with_vendor_hint() {
+        let headers = indoc! {"
+            X-RateLimit-Limit: 600
+            X-RateLimit-Remaining: 599
+            X-RateLimit-Reset: 1350085394
+        "};
+
+        // SendGrid and Discord can't be told apart without a hint
+        assert_eq!(
+            RateLimit::new(headers).unwrap().vendor(),
+            Some(Vendor::Discord)
+        );
+        assert_eq!(
+            RateLimit::new_checked(headers).unwrap().vendor(),
+            Some(Vendor::Discord)
+        );
+        let rate = RateLimit::new_with_vendor(headers, Vendor::SendGrid).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::SendGrid));
+        assert_eq!(rate.limit(), Some(600));
+
+        // Vimeo expects a date, not a timestamp
+        assert!(RateLimit::new_with_vendor(headers, Vendor::Vimeo).is_err());
+
+        let rate = RateLimit::new_with_vendor("Retry-After: 10", Vendor::Notion).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Notion));
+    }
+
+    #[test]
+    fn parse_from_pairs() {
+        let pairs = vec![
+            ("x-ratelimit-limit".to_string(), "5000".to_string()),
+            ("x-ratelimit-remaining".to_string(), "4987".to_string()),
+            ("x-ratelimit-reset".to_string(), "1350085394".to_string()),
+        ];
+
+        let rate = RateLimit::from_pairs(pairs).unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), Some(4987));
+    }
+
+    #[test]
+    fn parse_from_invalid_pairs() {
+        let pairs = vec![("x-ratelimit-limit".to_string(), "50\n00".to_string())];
+        assert!(RateLimit::from_pairs(pairs).is_err());
+    }
+
+    #[test]
+    fn parse_from_bytes() {
+        let rate = RateLimit::from_bytes(
+            b"x-ratelimit-limit: 5000\r\nx-ratelimit-remaining: 4987\r\nx-ratelimit-reset: 1350085394\r\n",
+        )
+        .unwrap();
+        assert_eq!(rate.limit(), Some(5000));
+        assert_eq!(rate.remaining(), Some(4987));
+
+        assert!(matches!(
+            RateLimit::from_bytes(b"Retry-After: 3\xff0"),
+            Err(Error::Utf8(_))
+        ));
+    }
+
+    #[test]
+    fn primary_limit_kind() {
+        let headers = indoc! {"
+            x-ratelimit-limit: 5000
+            x-ratelimit-remaining: 4987
+            x-ratelimit-reset: 1350085394
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.kind(), LimitKind::Primary);
+    }
 }
@@ -0,0 +1,44 @@
+//! Rate limits of named buckets
+use crate::headers::Headers;
+use crate::reset_time::ResetTime;
+
+/// A rate limit which applies to a named bucket
+///
+/// Some vendors have several independent rate limits and tell them apart by
+/// name, e.g. Github's `core` and `search` resources, Discord's buckets or
+/// Microsoft Graph's throttle scopes. Others limit several dimensions at
+/// once, e.g. OpenAI's `requests` and `tokens`. Keying by
+/// [`NamedLimit::name`] allows tracking each of them separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedLimit {
+    /// The name of the bucket (e.g. `core` or `search`)
+    pub name: String,
+    /// The maximum number of requests allowed in the time window, if known
+    pub limit: Option<usize>,
+    /// The number of requests remaining in the time window
+    pub remaining: usize,
+    /// The time at which the rate limit will be reset
+    pub reset: ResetTime,
+}
+
+impl NamedLimit {
+    /// Get the named limit of the given rate limit headers,
+    /// or `None` if the vendor didn't send a name
+    ///
+    /// The resource is used as the name if present, otherwise the
+    /// throttle scope. If other dimensions are limited as well (see
+    /// [`Headers::other_limits`]), the limit is named `requests` instead.
+    pub(crate) fn from_headers(headers: &Headers) -> Option<Self> {
+        let name = headers
+            .resource
+            .clone()
+            .or_else(|| headers.throttle_scope.clone())
+            .or_else(|| (!headers.other_limits.is_empty()).then(|| "requests".to_string()))?;
+        Some(NamedLimit {
+            name,
+            limit: headers.limit,
+            remaining: headers.remaining,
+            reset: headers.reset,
+        })
+    }
+}
@@ -1,8 +1,10 @@
+use std::cmp::Ordering;
+
 use crate::convert;
 use crate::error::{Error, Result};
 use headers::HeaderValue;
-use time::format_description::well_known::{Iso8601, Rfc2822};
-use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+use time::format_description::well_known::{Iso8601, Rfc2822, Rfc3339};
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
 /// The kind of rate limit reset time
 ///
@@ -13,20 +15,63 @@ use time::{Duration, OffsetDateTime, PrimitiveDateTime};
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ResetTimeKind {
     /// Number of seconds until rate limit is lifted
+    ///
+    /// Fractional values are rounded up to the next whole second.
+    /// Some servers send a date instead, so RFC 2822 and ISO 8601 dates
+    /// are accepted as a fallback.
     Seconds,
     /// Unix timestamp when rate limit will be lifted
+    ///
+    /// Fractional timestamps are rounded up to the next whole second.
     Timestamp,
     /// RFC 2822 date when rate limit will be lifted
     ImfFixdate,
     /// ISO 8601 date when rate limit will be lifted
     Iso8601,
+    /// RFC 3339 date when rate limit will be lifted
+    ///
+    /// Unlike [`ResetTimeKind::Iso8601`], this is strict: the date must
+    /// contain a `T` (or space) separator and an offset (e.g. `Z` or
+    /// `+02:00`). The date is normalized to UTC.
+    Rfc3339,
+    /// Go-style duration until the rate limit is lifted (e.g. `6m0s` or `1.5s`)
+    ///
+    /// Fractional values are rounded up to the next whole second.
+    GoDuration,
+    /// Either seconds or a Unix timestamp, depending on the magnitude of the value
+    ///
+    /// Values below [`ResetTimeKind::AUTO_SECONDS_THRESHOLD`] are treated as
+    /// seconds, all others as a Unix timestamp. This is a good default for
+    /// vendors which are not known to use one or the other.
+    /// Like with [`ResetTimeKind::Seconds`], dates are accepted as a fallback.
+    Auto,
+    /// Detect the format from the value itself
+    ///
+    /// The value is parsed like [`ResetTimeKind::Auto`] first, which covers
+    /// numbers and RFC 2822 and ISO 8601 dates. Otherwise, it is parsed as a
+    /// Go-style duration. This is useful when the vendor is unknown or
+    /// ambiguous.
+    Sniff,
+}
+
+impl ResetTimeKind {
+    /// Threshold used by [`ResetTimeKind::Auto`] to tell seconds and Unix
+    /// timestamps apart
+    ///
+    /// This is ten years worth of seconds. No rate limit window is that long,
+    /// and every Unix timestamp since 1980 is larger. So the two kinds of
+    /// values don't overlap in practice.
+    pub const AUTO_SECONDS_THRESHOLD: usize = 10 * 365 * 24 * 60 * 60;
 }
 
 /// Reset time of rate limiting
 ///
 /// There are different variants on how to specify reset times
 /// in rate limit headers. The most common ones are seconds and datetime.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd)]
+///
+/// Use [`ResetTime::cmp_at`] to order reset times by the point in time at
+/// which they occur, regardless of the variant.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ResetTime {
     /// Number of seconds until rate limit is lifted
     Seconds(usize),
@@ -35,6 +80,12 @@ pub enum ResetTime {
 }
 
 impl ResetTime {
+    /// A reset time which has already been reached, i.e. "retry now"
+    pub const ZERO: Self = ResetTime::Seconds(0);
+
+    /// Maximum number of seconds until a plausible reset (about 136 years)
+    pub const MAX_SECONDS: usize = u32::MAX as usize;
+
     /// Create a new reset time from a header value and a reset time kind
     ///
     /// # Errors
@@ -42,18 +93,81 @@ impl ResetTime {
     /// This function returns an error if the header value cannot be parsed
     /// or if the reset time kind is unknown.
     pub fn new(value: &HeaderValue, kind: ResetTimeKind) -> Result<Self> {
-        let value = value.to_str()?;
+        Self::parse(value.to_str()?, kind)
+    }
+
+    /// Create a relative reset time from a duration
+    ///
+    /// Only the whole seconds of the duration are kept.
+    /// Negative durations are clamped to zero.
+    #[must_use]
+    pub const fn from_duration(duration: Duration) -> Self {
+        let seconds = duration.whole_seconds();
+        if seconds <= 0 {
+            ResetTime::ZERO
+        } else {
+            #[allow(clippy::cast_possible_truncation)]
+            ResetTime::Seconds(seconds as usize)
+        }
+    }
+
+    /// Account for the time which passed since the reset time was parsed
+    ///
+    /// Relative reset times are decremented by the whole seconds of
+    /// `elapsed`, saturating at zero. Absolute reset times don't change.
+    pub fn age_by(&mut self, elapsed: Duration) {
+        if let ResetTime::Seconds(seconds) = self {
+            let elapsed = usize::try_from(elapsed.whole_seconds().max(0)).unwrap_or(usize::MAX);
+            *seconds = seconds.saturating_sub(elapsed);
+        }
+    }
+
+    /// Parse a reset time from a string value and a reset time kind
+    pub(crate) fn parse(value: &str, kind: ResetTimeKind) -> Result<Self> {
         match kind {
-            ResetTimeKind::Seconds => Ok(ResetTime::Seconds(convert::to_usize(value)?)),
+            ResetTimeKind::Seconds => match convert::to_seconds_ceil(value) {
+                Ok(seconds) => Ok(ResetTime::Seconds(seconds)),
+                Err(e) => Self::parse_date(value).ok_or(e),
+            },
             ResetTimeKind::Timestamp => Ok(Self::DateTime(
-                OffsetDateTime::from_unix_timestamp(convert::to_i64(value)?)
+                OffsetDateTime::from_unix_timestamp(convert::to_timestamp_ceil(value)?)
                     .map_err(Error::Time)?,
             )),
             ResetTimeKind::Iso8601 => {
+                // Respect the offset if there is one (e.g. `Z` or `+02:00`),
+                // otherwise assume UTC.
                 // https://github.com/time-rs/time/issues/378
-                let d = PrimitiveDateTime::parse(value, &Iso8601::PARSING).map_err(Error::Parse)?;
-                Ok(ResetTime::DateTime(d.assume_utc()))
+                let d = OffsetDateTime::parse(value, &Iso8601::PARSING).or_else(|_| {
+                    PrimitiveDateTime::parse(value, &Iso8601::PARSING)
+                        .map(PrimitiveDateTime::assume_utc)
+                        .map_err(Error::Parse)
+                })?;
+                Ok(ResetTime::DateTime(d))
+            }
+            ResetTimeKind::Rfc3339 => {
+                let d = OffsetDateTime::parse(value, &Rfc3339)?;
+                Ok(ResetTime::DateTime(d.to_offset(UtcOffset::UTC)))
+            }
+            ResetTimeKind::Auto => {
+                // Compare as `i64` to not overflow `usize` on 32-bit targets.
+                // Numbers (including fractional ones) at or above the
+                // threshold are timestamps. Smaller numbers and values which
+                // are not numbers at all (e.g. dates) are parsed as seconds.
+                match convert::to_timestamp_ceil(value) {
+                    Ok(v) if v >= ResetTimeKind::AUTO_SECONDS_THRESHOLD as i64 => {
+                        ResetTime::parse(value, ResetTimeKind::Timestamp)
+                    }
+                    _ => ResetTime::parse(value, ResetTimeKind::Seconds),
+                }
             }
+            ResetTimeKind::GoDuration => {
+                Ok(ResetTime::Seconds(convert::go_duration_seconds(value)?))
+            }
+            // `Auto` already falls back to dates, so only Go-style durations
+            // are left. If they don't match either, report why the value is
+            // not a number.
+            ResetTimeKind::Sniff => ResetTime::parse(value, ResetTimeKind::Auto)
+                .or_else(|e| ResetTime::parse(value, ResetTimeKind::GoDuration).map_err(|_| e)),
             ResetTimeKind::ImfFixdate => {
                 let d = PrimitiveDateTime::parse(value, &Rfc2822).map_err(Error::Parse)?;
                 Ok(ResetTime::DateTime(d.assume_utc()))
@@ -61,18 +175,71 @@ impl ResetTime {
         }
     }
 
+    /// Parse an RFC 2822 or ISO 8601 date, in this order
+    fn parse_date(value: &str) -> Option<Self> {
+        [ResetTimeKind::ImfFixdate, ResetTimeKind::Iso8601]
+            .into_iter()
+            .find_map(|kind| ResetTime::parse(value, kind).ok())
+    }
+
+    /// Convert the reset time to an absolute point in time,
+    /// using `now` as the reference for relative reset times.
+    pub(crate) fn to_datetime(self, now: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            ResetTime::Seconds(s) => i64::try_from(s)
+                .ok()
+                .and_then(|s| now.checked_add(Duration::seconds(s)))
+                .unwrap_or_else(|| PrimitiveDateTime::MAX.assume_utc()),
+            ResetTime::DateTime(d) => d,
+        }
+    }
+
     /// Get the number of seconds until the rate limit gets lifted.
+    ///
+    /// Reset times in the past yield zero. Implausibly distant reset times
+    /// (e.g. a timestamp in milliseconds which was misparsed as seconds)
+    /// saturate at [`ResetTime::MAX_SECONDS`]. Use [`ResetTime::try_seconds`]
+    /// to detect those.
     #[must_use]
     pub fn seconds(&self) -> usize {
-        match self {
+        self.seconds_with_reference(OffsetDateTime::now_utc())
+    }
+
+    /// Get the number of seconds until the rate limit gets lifted,
+    /// relative to the given point in time.
+    ///
+    /// Absolute reset times are set by the server's clock. If the local clock
+    /// is skewed, pass the server's time (e.g. from the `Date` header) as
+    /// `now` to get the correct number of seconds. Relative reset times do
+    /// not depend on `now`.
+    ///
+    /// Like [`ResetTime::seconds`], this saturates at [`ResetTime::MAX_SECONDS`].
+    #[must_use]
+    pub fn seconds_with_reference(&self, now: OffsetDateTime) -> usize {
+        self.seconds_since(now).unwrap_or(Self::MAX_SECONDS)
+    }
+
+    /// Get the number of seconds until the rate limit gets lifted,
+    /// or `None` if the reset time is implausibly far in the future.
+    ///
+    /// Reset times more than [`ResetTime::MAX_SECONDS`] in the future are
+    /// considered implausible. Reset times in the past yield `Some(0)`.
+    #[must_use]
+    pub fn try_seconds(&self) -> Option<usize> {
+        self.seconds_since(OffsetDateTime::now_utc())
+    }
+
+    /// Get the number of seconds from `now` until the rate limit gets lifted,
+    /// or `None` if that is more than [`ResetTime::MAX_SECONDS`]
+    fn seconds_since(&self, now: OffsetDateTime) -> Option<usize> {
+        let seconds = match self {
             ResetTime::Seconds(s) => *s,
-            // OffsetDateTime is not timezone aware, so we need to convert it to UTC
-            // and then convert it to seconds.
-            // There are no negative values in the seconds field, so we can safely
-            // cast it to usize.
-            #[allow(clippy::cast_possible_truncation)]
-            ResetTime::DateTime(d) => (*d - OffsetDateTime::now_utc()).whole_seconds() as usize,
-        }
+            ResetTime::DateTime(d) => {
+                let seconds = (*d - now).whole_seconds();
+                usize::try_from(seconds.max(0)).unwrap_or(usize::MAX)
+            }
+        };
+        (seconds <= Self::MAX_SECONDS).then_some(seconds)
     }
 
     /// Convert reset time to duration
@@ -85,4 +252,53 @@ impl ResetTime {
             }
         }
     }
+
+    /// Get the reset time as a Unix timestamp (seconds since the epoch)
+    ///
+    /// Relative reset times are taken relative to now. This is a compact
+    /// representation for logging and persistence, e.g. as a cache key.
+    #[must_use]
+    pub fn as_unix_timestamp(&self) -> i64 {
+        self.to_datetime(OffsetDateTime::now_utc()).unix_timestamp()
+    }
+
+    /// Check if the rate limit has already been lifted,
+    /// i.e. the reset time is now or in the past.
+    ///
+    /// Relative reset times are only considered passed if they are zero.
+    #[must_use]
+    pub fn has_passed(&self) -> bool {
+        match self {
+            ResetTime::Seconds(s) => *s == 0,
+            ResetTime::DateTime(d) => *d <= OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Check if the reset time is still in the future
+    ///
+    /// This is the opposite of [`ResetTime::has_passed`].
+    #[must_use]
+    pub fn is_future(&self) -> bool {
+        !self.has_passed()
+    }
+
+    /// Compare two reset times by the point in time at which they occur
+    ///
+    /// Relative reset times (`Seconds`) are interpreted relative to `now`.
+    /// This is not an [`Ord`] implementation, because the result depends on
+    /// the reference time. Pass the same `now` for all comparisons, e.g.
+    /// when sorting, so that the order is consistent.
+    #[must_use]
+    pub fn cmp_at(&self, other: &Self, now: OffsetDateTime) -> Ordering {
+        match (self, other) {
+            (ResetTime::Seconds(a), ResetTime::Seconds(b)) => a.cmp(b),
+            _ => self.to_datetime(now).cmp(&other.to_datetime(now)),
+        }
+    }
+}
+
+impl Default for ResetTime {
+    fn default() -> Self {
+        Self::ZERO
+    }
 }
@@ -8,16 +8,40 @@ use time::{format_description::well_known::Rfc2822, Date};
 
 use crate::{
     casesensitive_headermap::CaseSensitiveHeaderMap,
+    convert,
+    headers::Vendor,
     reset_time::{ResetTime, ResetTimeKind},
 };
 
 use super::error::{Error, Result};
 
+/// Name of the `Retry-After` header
+const RETRY_AFTER: &str = "Retry-After";
+
+/// Accepted spellings of the `Retry-After` header
+pub(crate) const RETRY_AFTER_HEADERS: [&str; 2] = [RETRY_AFTER, "retry-after"];
+
 /// HTTP rate limits as parsed from header values
-#[derive(Copy, Clone, Debug, PartialEq)]
+///
+/// Like [`crate::headers::Headers`], this can't be built with a struct
+/// literal outside of this crate. Use [`RateLimit::new`] instead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct RateLimit {
     /// Time at which the rate limit will be reset
     pub reset: ResetTime,
+    /// Vendor which sent the `Retry-After` header, if known
+    ///
+    /// The header is the same for all vendors, so this is `None` unless
+    /// it is set by the caller (e.g. [`Vendor::Notion`]).
+    pub vendor: Option<Vendor>,
+    /// Status code of the response which sent the `Retry-After` header, if known
+    ///
+    /// `Retry-After` is also sent with `503 Service Unavailable` (e.g. during
+    /// maintenance) and on redirects, which is not a rate limit.
+    pub status: Option<http::StatusCode>,
+    /// Name of the header which was used to parse the rate limit
+    pub header: &'static str,
 }
 
 impl RateLimit {
@@ -25,29 +49,105 @@ impl RateLimit {
     ///
     /// See <https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After>
     pub fn new<T: Into<CaseSensitiveHeaderMap>>(headers: T) -> std::result::Result<Self, Error> {
-        let headers = headers.into();
-        let reset = match Self::get_retry_after_header(&headers) {
-            Some(retry_after) => {
-                if Date::parse(retry_after.to_str()?, &Rfc2822).is_ok() {
-                    ResetTime::new(retry_after, ResetTimeKind::ImfFixdate)?
-                } else {
-                    ResetTime::new(retry_after, ResetTimeKind::Seconds)?
-                }
+        Self::from_header_map(&headers.into())
+    }
+
+    /// Rate limit implementation based on `Retry-After` header value,
+    /// annotated with the vendor which sent it
+    ///
+    /// Use this if the vendor is known from the context (e.g. the host),
+    /// because it cannot be detected from the `Retry-After` header itself.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the header is missing or invalid.
+    pub fn new_with_vendor<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+        vendor: Vendor,
+    ) -> std::result::Result<Self, Error> {
+        Ok(RateLimit {
+            vendor: Some(vendor),
+            ..Self::from_header_map(&headers.into())?
+        })
+    }
+
+    /// Rate limit implementation based on `Retry-After` header value,
+    /// annotated with the status code of the response
+    ///
+    /// See [`RateLimit::is_rate_limit`] for why the status code matters.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the header is missing or invalid.
+    pub fn new_with_status<T: Into<CaseSensitiveHeaderMap>>(
+        headers: T,
+        status: http::StatusCode,
+    ) -> std::result::Result<Self, Error> {
+        Ok(RateLimit {
+            status: Some(status),
+            ..Self::from_header_map(&headers.into())?
+        })
+    }
+
+    /// Rate limit implementation based on a borrowed header map.
+    ///
+    /// See [`RateLimit::new`] for details.
+    pub(crate) fn from_header_map(headers: &CaseSensitiveHeaderMap) -> Result<Self> {
+        let (header, reset) = match Self::get_retry_after_header(headers) {
+            // An empty value is malformed, not an invalid number of seconds
+            Some((_, retry_after)) if retry_after.as_bytes().trim_ascii().is_empty() => {
+                return Err(Error::InvalidRetryAfter(
+                    String::from_utf8_lossy(retry_after.as_bytes()).into_owned(),
+                ))
+            }
+            Some((header, retry_after)) => (
+                header,
+                convert::parse_header(header, retry_after, |value| {
+                    if Date::parse(value, &Rfc2822).is_ok() {
+                        ResetTime::parse(value, ResetTimeKind::ImfFixdate)
+                    } else {
+                        // Fractional seconds (e.g. Dropbox) are rounded up.
+                        // Some servers append a comment (e.g. `120; foo`),
+                        // so only the leading token is used.
+                        let seconds = value
+                            .trim()
+                            .split(|c: char| c == ';' || c.is_whitespace())
+                            .next()
+                            .unwrap_or(value);
+                        ResetTime::parse(seconds, ResetTimeKind::Seconds)
+                    }
+                })?,
+            ),
+            None => {
+                return Err(Error::MissingRetryAfter {
+                    candidates: RETRY_AFTER_HEADERS
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                })
             }
-            None => return Err(Error::MissingRetryAfter),
         };
 
-        Ok(RateLimit { reset })
+        #[cfg(feature = "tracing")]
+        tracing::trace!(?reset, "parsed Retry-After header");
+        Ok(RateLimit {
+            reset,
+            vendor: None,
+            status: None,
+            header,
+        })
     }
 
     /// Get the Retry-After header value
     ///
     /// This does not need to be case sensitive because the header name is
     /// not ambiguous.
-    fn get_retry_after_header(header_map: &CaseSensitiveHeaderMap) -> Option<&HeaderValue> {
-        header_map
-            .get("Retry-After")
-            .or_else(|| header_map.get("retry-after"))
+    fn get_retry_after_header(
+        header_map: &CaseSensitiveHeaderMap,
+    ) -> Option<(&'static str, &HeaderValue)> {
+        RETRY_AFTER_HEADERS
+            .iter()
+            .find_map(|&name| Some((name, header_map.get(name)?)))
     }
 
     /// Get the time at which the rate limit will be reset
@@ -55,13 +155,36 @@ impl RateLimit {
     pub const fn reset(&self) -> ResetTime {
         self.reset
     }
+
+    /// Get the vendor which sent the `Retry-After` header, if known
+    #[must_use]
+    pub const fn vendor(&self) -> Option<Vendor> {
+        self.vendor
+    }
+
+    /// Get the status code of the response, if known
+    #[must_use]
+    pub const fn status(&self) -> Option<http::StatusCode> {
+        self.status
+    }
+
+    /// Check if the `Retry-After` header was sent because of rate limiting
+    ///
+    /// This is only the case for `429 Too Many Requests`. A `Retry-After`
+    /// header on a `503 Service Unavailable` usually means that the service
+    /// is down (e.g. for maintenance), which warrants a different backoff
+    /// strategy. Returns `false` if the status code is unknown.
+    #[must_use]
+    pub fn is_rate_limit(&self) -> bool {
+        self.status == Some(http::StatusCode::TOO_MANY_REQUESTS)
+    }
 }
 
 impl FromStr for RateLimit {
     type Err = Error;
 
     fn from_str(map: &str) -> Result<Self> {
-        RateLimit::new(CaseSensitiveHeaderMap::from_str(map)?)
+        RateLimit::from_header_map(&CaseSensitiveHeaderMap::from_str(map)?)
     }
 }
 
@@ -75,8 +198,9 @@ mod tests {
     #[test]
     fn parse_retry_after_seconds() {
         let map = CaseSensitiveHeaderMap::from_str("Retry-After: 30").unwrap();
-        let retry = RateLimit::get_retry_after_header(&map).unwrap();
+        let (name, retry) = RateLimit::get_retry_after_header(&map).unwrap();
 
+        assert_eq!(name, "Retry-After");
         assert_eq!("30", retry);
     }
 
@@ -98,6 +222,39 @@ mod tests {
 
         let rate = RateLimit::from_str(headers).unwrap();
         assert_eq!(rate.reset(), ResetTime::Seconds(19));
+        assert_eq!(rate.header, "retry-after");
+    }
+
+    #[test]
+    fn retry_after_fractional_seconds() {
+        let headers = indoc! {"
+            Retry-After: 2.5
+        "};
+
+        let rate = RateLimit::from_str(headers).unwrap();
+        assert_eq!(rate.reset(), ResetTime::Seconds(3));
+    }
+
+    #[test]
+    fn retry_after_seconds_with_comment() {
+        for value in ["120 ", "120; foo", " 120 ;foo", "120 seconds"] {
+            let mut map = CaseSensitiveHeaderMap::new();
+            map.insert(RETRY_AFTER.to_string(), HeaderValue::from_static(value));
+            let rate = RateLimit::new(map).unwrap();
+            assert_eq!(rate.reset(), ResetTime::Seconds(120), "{value:?}");
+        }
+
+        assert!(RateLimit::from_str("Retry-After: ; 120").is_err());
+    }
+
+    #[test]
+    fn retry_after_empty() {
+        let mut map = CaseSensitiveHeaderMap::new();
+        map.insert(RETRY_AFTER.to_string(), HeaderValue::from_static(""));
+        assert!(matches!(
+            RateLimit::new(map),
+            Err(Error::InvalidRetryAfter(value)) if value.is_empty()
+        ));
     }
 
     #[test]
@@ -112,4 +269,53 @@ mod tests {
             ResetTime::DateTime(datetime!(1999-12-31 23:59:59 UTC))
         );
     }
+
+    #[test]
+    fn retry_after_vendor() {
+        let rate = RateLimit::from_str("Retry-After: 30").unwrap();
+        assert_eq!(rate.vendor(), None);
+
+        let rate = RateLimit::new_with_vendor("Retry-After: 30", Vendor::Notion).unwrap();
+        assert_eq!(rate.vendor(), Some(Vendor::Notion));
+        assert_eq!(rate.reset(), ResetTime::Seconds(30));
+
+        assert!(RateLimit::new_with_vendor("", Vendor::Notion).is_err());
+    }
+
+    #[test]
+    fn retry_after_status() {
+        let rate = RateLimit::from_str("Retry-After: 30").unwrap();
+        assert_eq!(rate.status(), None);
+        assert!(!rate.is_rate_limit());
+
+        let rate =
+            RateLimit::new_with_status("Retry-After: 30", http::StatusCode::TOO_MANY_REQUESTS)
+                .unwrap();
+        assert_eq!(rate.status(), Some(http::StatusCode::TOO_MANY_REQUESTS));
+        assert!(rate.is_rate_limit());
+
+        let rate =
+            RateLimit::new_with_status("Retry-After: 3600", http::StatusCode::SERVICE_UNAVAILABLE)
+                .unwrap();
+        assert!(!rate.is_rate_limit());
+        assert_eq!(rate.reset(), ResetTime::Seconds(3600));
+    }
+
+    #[test]
+    fn retry_after_invalid() {
+        let headers = indoc! {"
+            Retry-After: soon
+        "};
+
+        assert!(matches!(
+            RateLimit::from_str(headers),
+            Err(Error::InvalidValueFor { header, value }) if header == "Retry-After" && value == "soon"
+        ));
+
+        // The error names the header which was found
+        assert!(matches!(
+            RateLimit::from_str("retry-after: soon"),
+            Err(Error::InvalidValueFor { header, .. }) if header == "retry-after"
+        ));
+    }
 }
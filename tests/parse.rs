@@ -4,8 +4,6 @@ mod cli {
     use rate_limits::{RateLimit, ResetTime, Vendor};
     use time::{Duration, OffsetDateTime};
 
-    use rate_limits::headers;
-
     #[test]
     fn test_example() {
         let mut headers = HeaderMap::new();
@@ -13,17 +11,25 @@ mod cli {
         headers.insert("X-RATELIMIT-REMAINING", "4987".parse().unwrap());
         headers.insert("X-RATELIMIT-RESET", "1350085394".parse().unwrap());
 
+        let RateLimit::Rfc6585(rate) = RateLimit::new(headers).unwrap() else {
+            panic!("expected rate limit headers");
+        };
+        assert_eq!(rate.limit, Some(5000));
+        assert_eq!(rate.remaining, 4987);
+        assert_eq!(rate.used, None);
+        assert_eq!(
+            rate.reset,
+            ResetTime::DateTime(OffsetDateTime::from_unix_timestamp(1350085394).unwrap())
+        );
+        assert_eq!(rate.window, Some(Duration::HOUR));
+        assert_eq!(rate.vendor, Vendor::Github);
         assert_eq!(
-            RateLimit::new(headers).unwrap(),
-            RateLimit::Rfc6585(headers::Headers {
-                limit: 5000,
-                remaining: 4987,
-                reset: ResetTime::DateTime(
-                    OffsetDateTime::from_unix_timestamp(1350085394).unwrap()
-                ),
-                window: Some(Duration::HOUR),
-                vendor: Vendor::Github
-            }),
+            rate.matched_headers,
+            vec![
+                "x-ratelimit-remaining",
+                "x-ratelimit-limit",
+                "x-ratelimit-reset",
+            ]
         );
     }
 }